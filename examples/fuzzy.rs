@@ -15,7 +15,7 @@ use tui::{
 };
 use tui_input::backend::crossterm::EventHandler;
 use tui_input::Input;
-use tunik::fuzzy_list::{FuzzyList, FuzzyListItem, FuzzyListState};
+use tunik::fuzzy_list::{FuzzyList, FuzzyListItem, FuzzyListState, NavOutcome};
 
 enum InputMode {
     Normal,
@@ -114,7 +114,11 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                         return Ok(());
                     }
                     KeyCode::Up => {
-                        app.list_state.decrement_selected();
+                        let outcome = app.list_state.decrement_selected();
+                        // moving past the first item gives focus back to the filter input
+                        if outcome == NavOutcome::EscapeTop {
+                            app.input_mode = InputMode::Editing;
+                        }
                     }
                     KeyCode::Down => {
                         app.list_state.increment_selected();
@@ -123,12 +127,14 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                 },
                 InputMode::Editing => match key.code {
                     KeyCode::Enter => {
-                        // set filter here
-                        app.list_state.set_filter(Some(app.input.value()));
-                        app.input.reset();
+                        app.input_mode = InputMode::Normal;
                     }
                     KeyCode::Esc => {
-                        app.input_mode = InputMode::Normal;
+                        // first escape clears the filter, second one stops editing
+                        if app.list_state.escape() == NavOutcome::RequestClose {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        app.input.reset();
                     }
                     KeyCode::Up => {
                         app.list_state.decrement_selected();
@@ -138,6 +144,8 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                     }
                     _ => {
                         app.input.handle_event(&Event::Key(key));
+                        // filter as the user types
+                        app.list_state.set_filter_live(app.input.value());
                     }
                 },
             }
@@ -174,9 +182,9 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             vec![
                 Span::raw("Press "),
                 Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" to stop filtering, "),
+                Span::raw(" to clear or stop filtering, "),
                 Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" to filter list"),
+                Span::raw(" to browse filtered list"),
             ],
             Style::default(),
         ),
@@ -215,6 +223,10 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
 
     let cities_widget = FuzzyList::new(app.list_state.get_items())
         .block(Block::default().borders(Borders::ALL).title("Cities"))
-        .highlight_style(Style::default().bg(Color::Red));
+        .highlight_style(Style::default().bg(Color::Red))
+        .summary_row(Some(Spans::from(Span::styled(
+            format!("{} cities", app.list_state.effective_len()),
+            Style::default().add_modifier(Modifier::DIM),
+        ))));
     f.render_stateful_widget(cities_widget, chunks[2], &mut app.list_state);
 }
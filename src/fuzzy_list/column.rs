@@ -0,0 +1,31 @@
+use tui::{
+    layout::{Alignment, Constraint},
+    text::Spans,
+};
+
+/// A column of a [`FuzzyList`](super::FuzzyList) rendering items built with
+/// [`FuzzyListItem::cells`](super::FuzzyListItem::cells)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column<'a> {
+    pub(super) header: Spans<'a>,
+    pub(super) width: Constraint,
+    pub(super) alignment: Alignment,
+}
+
+impl<'a> Column<'a> {
+    pub fn new<T>(header: T, width: Constraint) -> Column<'a>
+    where
+        T: Into<Spans<'a>>,
+    {
+        Column {
+            header: header.into(),
+            width,
+            alignment: Alignment::Left,
+        }
+    }
+
+    pub fn alignment(mut self, alignment: Alignment) -> Column<'a> {
+        self.alignment = alignment;
+        self
+    }
+}
@@ -0,0 +1,173 @@
+use super::{FuzzyListState, NavOutcome};
+
+/// Several [`FuzzyListState`]s, e.g. result sections such as files and commands, filtered by one
+/// query and navigated as a single list
+#[derive(Clone, Default)]
+pub struct FuzzyGroup<'a> {
+    states: Vec<FuzzyListState<'a>>,
+    /// section holding the selection
+    active: usize,
+}
+
+impl<'a> FuzzyGroup<'a> {
+    pub fn new(states: Vec<FuzzyListState<'a>>) -> FuzzyGroup<'a> {
+        FuzzyGroup { states, active: 0 }
+    }
+
+    pub fn states(&self) -> &[FuzzyListState<'a>] {
+        &self.states
+    }
+
+    /// State of section `section`, to render it with its own [`FuzzyList`](super::FuzzyList)
+    pub fn state_mut(&mut self, section: usize) -> Option<&mut FuzzyListState<'a>> {
+        self.states.get_mut(section)
+    }
+
+    /// Applies `filter` to every section. The selection starts over from the first section, or
+    /// the first one that selected its top result
+    pub fn set_filter(&mut self, filter: Option<&str>) {
+        for state in self.states.iter_mut() {
+            state.set_filter(filter);
+        }
+        self.active = self
+            .states
+            .iter()
+            .position(|s| s.has_selection() && !s.filtered_out())
+            .unwrap_or(0);
+        for (section, state) in self.states.iter_mut().enumerate() {
+            if section != self.active {
+                state.clear_selection();
+            }
+        }
+    }
+
+    /// Section holding the selection and the selected position within it
+    pub fn selected(&self) -> Option<(usize, usize)> {
+        let state = self.states.get(self.active)?;
+        state.selected().map(|selected| (self.active, selected))
+    }
+
+    /// Selects the next item, moving on to the first item of the following sections past the
+    /// end of a section
+    pub fn increment_selected(&mut self) -> NavOutcome {
+        match self
+            .states
+            .get_mut(self.active)
+            .filter(|s| !s.filtered_out())
+            .map(|s| s.increment_selected())
+        {
+            Some(NavOutcome::EscapeBottom) | None => {}
+            Some(outcome) => return outcome,
+        }
+        for section in self.active + 1..self.states.len() {
+            let state = &mut self.states[section];
+            // sections the filter left empty are skipped
+            if state.filtered_out() {
+                continue;
+            }
+            state.clear_selection();
+            if state.increment_selected() == NavOutcome::Moved {
+                self.activate(section);
+                return NavOutcome::Moved;
+            }
+        }
+        NavOutcome::EscapeBottom
+    }
+
+    /// Selects the previous item, moving on to the last item of the preceding sections past the
+    /// start of a section
+    pub fn decrement_selected(&mut self) -> NavOutcome {
+        match self
+            .states
+            .get_mut(self.active)
+            .filter(|s| !s.filtered_out())
+            .map(|s| s.decrement_selected())
+        {
+            Some(NavOutcome::EscapeTop) | None => {}
+            Some(outcome) => return outcome,
+        }
+        for section in (0..self.active.min(self.states.len())).rev() {
+            let state = &mut self.states[section];
+            if state.filtered_out() {
+                continue;
+            }
+            state.select_last();
+            if state.has_selection() {
+                self.activate(section);
+                return NavOutcome::Moved;
+            }
+        }
+        NavOutcome::EscapeTop
+    }
+
+    /// Confirms the selected item, returning its section and original index
+    pub fn confirm(&mut self) -> Option<(usize, usize)> {
+        let state = self.states.get_mut(self.active)?;
+        state.confirm().map(|index| (self.active, index))
+    }
+
+    /// Moves the selection to `section`, deselecting the previously active one
+    fn activate(&mut self, section: usize) {
+        if section != self.active {
+            if let Some(state) = self.states.get_mut(self.active) {
+                state.clear_selection();
+            }
+        }
+        self.active = section;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy_list::FuzzyListItem;
+
+    fn group(sections: &[&[&'static str]]) -> FuzzyGroup<'static> {
+        FuzzyGroup::new(
+            sections
+                .iter()
+                .map(|items| {
+                    FuzzyListState::with_items(
+                        items.iter().map(|&i| FuzzyListItem::new(i)).collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn filtering_restarts_from_the_first_section() {
+        let mut group = group(&[&["apple", "banana"], &["apricot", "cherry"]]);
+        group.increment_selected();
+        group.increment_selected();
+        group.increment_selected();
+        assert_eq!(group.selected(), Some((1, 0)));
+
+        group.set_filter(Some("ap"));
+        assert_eq!(group.selected(), None);
+        group.increment_selected();
+        assert_eq!(group.selected(), Some((0, 0)));
+        group.increment_selected();
+        assert_eq!(group.selected(), Some((1, 0)));
+    }
+
+    #[test]
+    fn sections_without_matches_are_skipped() {
+        let mut group = group(&[&["apple"], &["kiwi", "cherry"], &["pineapple"]]);
+        group.set_filter(Some("apple"));
+        group.increment_selected();
+        assert_eq!(group.selected(), Some((0, 0)));
+        group.increment_selected();
+        assert_eq!(group.selected(), Some((2, 0)));
+        assert_eq!(group.increment_selected(), NavOutcome::EscapeBottom);
+        group.decrement_selected();
+        assert_eq!(group.selected(), Some((0, 0)));
+        assert_eq!(group.decrement_selected(), NavOutcome::EscapeTop);
+
+        // a first section without matches is skipped as well
+        group.set_filter(Some("cherry"));
+        group.increment_selected();
+        assert_eq!(group.selected(), Some((1, 0)));
+        assert_eq!(group.increment_selected(), NavOutcome::EscapeBottom);
+    }
+}
@@ -1,16 +1,176 @@
-use std::rc::Rc;
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    fmt, iter, mem,
+    ops::Range,
+    rc::Rc,
+    time::{Duration, Instant, SystemTime},
+};
 
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use tui::{
     buffer::Buffer,
-    layout::{Corner, Rect},
-    style::{Color, Style},
+    layout::{Alignment, Constraint, Corner, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, StatefulWidget, Widget},
+    widgets::{Block, Paragraph, StatefulWidget, Widget, Wrap},
 };
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+mod column;
+mod group;
+
+pub use column::Column;
+pub use group::FuzzyGroup;
+
+/// Frames of the spinner shown for loading items
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Result of navigating with [`FuzzyListState::increment_selected`],
+/// [`FuzzyListState::decrement_selected`] or [`FuzzyListState::escape`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavOutcome {
+    /// Selection moved to another item
+    Moved,
+    /// Navigation went past the first item, host may move focus elsewhere (e.g. filter input)
+    EscapeTop,
+    /// Navigation went past the last item
+    EscapeBottom,
+    /// Escape cleared the active filter
+    Cleared,
+    /// Escape asks the host to close the list
+    RequestClose,
+}
+
+/// How much of the content around matched characters is highlighted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightGranularity {
+    /// Only the matched characters
+    #[default]
+    Char,
+    /// Every word containing a matched character
+    Word,
+}
+
+/// Presents matched characters of an item, replacing the default styling with
+/// `filter_style` when set with [`FuzzyListItem::highlight_renderer`]
+pub trait HighlightRenderer {
+    /// Builds the spans for `content` given the character indices matched by the filter
+    fn render_highlight(&self, content: &str, indices: &[usize]) -> Vec<Span<'static>>;
+}
+
+/// Placement of always visible items among filtered results
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AlwaysVisiblePosition {
+    /// Before the matching items
+    Top,
+    /// After the matching items
+    #[default]
+    Bottom,
+}
+
+/// Case sensitivity of the default matcher
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    /// Case sensitive only when the filter contains an uppercase character
+    #[default]
+    Smart,
+    /// Never case sensitive
+    Ignore,
+    /// Always case sensitive
+    Respect,
+}
+
+impl CaseMode {
+    /// Mode following this one when cycling with [`FuzzyListState::toggle_case_mode`]
+    pub fn next(self) -> CaseMode {
+        match self {
+            CaseMode::Smart => CaseMode::Ignore,
+            CaseMode::Ignore => CaseMode::Respect,
+            CaseMode::Respect => CaseMode::Smart,
+        }
+    }
+
+    fn matcher(self) -> SkimMatcherV2 {
+        let matcher = SkimMatcherV2::default();
+        match self {
+            CaseMode::Smart => matcher.smart_case(),
+            CaseMode::Ignore => matcher.ignore_case(),
+            CaseMode::Respect => matcher.respect_case(),
+        }
+    }
+}
+
+/// Order of the items shown while a filter is active
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OrderMode {
+    /// Best matches first, ties keep their original order
+    #[default]
+    MatchScore,
+    /// Same order as the items
+    Original,
+    /// Sorted by search text with a plain, locale-naive string compare
+    Alphabetical,
+}
+
+/// Changes to [`FuzzyListState`] collected for hosts that sync the list into an external store,
+/// once enabled with [`FuzzyListState::set_record_events`] and drained with
+/// [`FuzzyListState::take_events`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateEvent {
+    /// Active filter changed to the given value
+    FilterChanged(Option<String>),
+    /// Selected index changed to the given value
+    SelectionChanged(Option<usize>),
+    /// Content of the items changed
+    ItemsChanged,
+    /// Active filter was emptied, e.g. the user deleted every character of the query
+    FilterEmptied,
+    /// Active filter was cancelled with `set_filter(None)`
+    FilterCleared,
+    /// Matching items against a filter started
+    FilterStarted,
+    /// Matching items against a filter completed
+    FilterCompleted {
+        /// Number of items that matched
+        matched: usize,
+        /// Time spent matching
+        duration: Duration,
+    },
+}
+
+/// Translates a character index of a search key to the matching character index of the content
+pub type SearchKeyMap = Rc<dyn Fn(usize) -> Option<usize>>;
+
+/// Derives a string key from an item, used to compare items by identity
+pub type ItemKey<'a> = Rc<dyn Fn(&FuzzyListItem<'a>) -> String + 'a>;
+
+/// Profiling information collected when enabled with [`FuzzyListState::set_collect_stats`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// Time spent matching items during the last filter
+    pub filter_duration: Duration,
+    /// Number of items matched against the last filter
+    pub items_scanned: usize,
+    /// Number of rows drawn by the last render
+    pub rows_rendered: usize,
+}
+
+/// Turns the content of a span into the text matched against filters,
+/// set with [`FuzzyListState::set_match_transform`]
+pub type MatchTransform = Rc<dyn Fn(&str) -> String>;
+
+/// Constraints the state applies on top of its matcher
+#[derive(Clone, Copy, Default)]
+struct MatchOptions<'t> {
+    max_gap: Option<usize>,
+    transform: Option<&'t MatchTransform>,
+}
+
 #[derive(Clone)]
 pub struct FuzzyListState<'a> {
     offset: usize,
@@ -18,8 +178,87 @@ pub struct FuzzyListState<'a> {
     filter: Option<String>,
     items: Rc<Vec<FuzzyListItem<'a>>>,
     filtered: Rc<Vec<FuzzyListItem<'a>>>,
+    /// original item index of each filtered item
+    filtered_indices: Rc<Vec<usize>>,
     /// matcher algorithm
     matcher: Rc<dyn FuzzyMatcher>,
+    /// changes not yet taken by the host
+    events: Vec<StateEvent>,
+    /// called with the original index of the confirmed item
+    on_confirm: Option<Rc<dyn Fn(usize)>>,
+    /// items with an already seen key are dropped when items are set
+    dedup_key: Option<ItemKey<'a>>,
+    /// animation frame counter advanced by `tick`
+    ticks: usize,
+    /// value of `ticks` when the items were last filtered
+    filtered_at: usize,
+    /// whether ancestors of matching items are kept when filtering
+    keep_ancestors: bool,
+    /// number of items that matched the filter
+    match_count: usize,
+    /// minimum filter length for live filtering
+    live_min_len: usize,
+    /// whether a unique match is confirmed as soon as filtering finds it
+    auto_accept_single: bool,
+    /// range of item indices considered when filtering
+    filter_range: Option<Range<usize>>,
+    /// whether filtering and rendering record stats
+    collect_stats: bool,
+    stats: Stats,
+    /// whether escape clears the filter before requesting to close
+    staged_escape: bool,
+    /// number of columns focus cycles through
+    focus_columns: usize,
+    /// focused column of the selected row
+    focused_column: usize,
+    /// where always visible items are placed while filtering
+    always_visible_position: AlwaysVisiblePosition,
+    /// order of the filtered items
+    order_mode: OrderMode,
+    /// display width of each item, computed on demand and reset when items change
+    item_widths: RefCell<Option<Vec<usize>>>,
+    /// largest number of skipped characters allowed between matched characters
+    max_gap: Option<usize>,
+    /// case sensitivity of the default matcher
+    case_mode: CaseMode,
+    /// whether the top result is selected after each filter
+    auto_select_top: bool,
+    /// turns the content of each span into the text seen by the matcher
+    match_transform: Option<MatchTransform>,
+    /// original index of the item confirmed last time the list was used
+    last_confirmed: Option<usize>,
+    /// whether the offset was scrolled away from the selection, which then may leave the viewport
+    detached: bool,
+    /// number of rows available to items during the last render
+    viewport_height: usize,
+    /// whether filtering keeps every item, only highlighting matches
+    passthrough: bool,
+    /// sum of the heights of the displayed items, reset when items or the filter change
+    cached_total_height: Cell<Option<usize>>,
+    /// identifies the selected item across `set_items` calls
+    identity_key: Option<ItemKey<'a>>,
+    /// builds the exact text matched for each item
+    haystack_fn: Option<ItemKey<'a>>,
+    /// quiet period before a live filter is applied
+    debounce: Option<Duration>,
+    /// latest live filter waiting for the debounce period, with the time it was typed
+    pending_filter: Option<(String, Instant)>,
+    /// screen rows of each item drawn by the last render, with its displayed position
+    rendered_rows: Vec<(Range<u16>, usize)>,
+    /// whether the selection sticks to the last item as items are appended
+    follow: bool,
+    /// whether the matcher was supplied by the host, which is then never replaced by the default one
+    custom_matcher: bool,
+    /// orders filtered items the sort considers equal, before their original order
+    tiebreak_key: Option<ItemKey<'a>>,
+    /// score of each filtered item, `None` for items shown without matching
+    filtered_scores: Rc<Vec<Option<i64>>>,
+    /// original index of the only item the last filter matched and shows
+    unique_match: Option<usize>,
+    /// whether changes are recorded for `take_events`
+    record_events: bool,
+    /// column the minimap was drawn in by the last render
+    minimap_area: Option<Rect>,
 }
 
 impl<'a> Default for FuzzyListState<'a> {
@@ -30,20 +269,63 @@ impl<'a> Default for FuzzyListState<'a> {
             filter: None,
             items: Rc::new(vec![]),
             filtered: Rc::new(vec![]),
+            filtered_indices: Rc::new(vec![]),
             matcher: Rc::new(SkimMatcherV2::default()),
+            events: vec![],
+            on_confirm: None,
+            dedup_key: None,
+            ticks: 0,
+            filtered_at: 0,
+            keep_ancestors: false,
+            match_count: 0,
+            live_min_len: 0,
+            auto_accept_single: false,
+            filter_range: None,
+            collect_stats: false,
+            stats: Stats::default(),
+            staged_escape: true,
+            focus_columns: 0,
+            focused_column: 0,
+            always_visible_position: AlwaysVisiblePosition::Bottom,
+            order_mode: OrderMode::MatchScore,
+            item_widths: RefCell::new(None),
+            max_gap: None,
+            case_mode: CaseMode::Smart,
+            auto_select_top: false,
+            match_transform: None,
+            last_confirmed: None,
+            detached: false,
+            viewport_height: 0,
+            passthrough: false,
+            cached_total_height: Cell::new(None),
+            identity_key: None,
+            haystack_fn: None,
+            debounce: None,
+            pending_filter: None,
+            rendered_rows: vec![],
+            follow: false,
+            custom_matcher: false,
+            tiebreak_key: None,
+            filtered_scores: Rc::new(vec![]),
+            unique_match: None,
+            record_events: false,
+            minimap_area: None,
         }
     }
 }
 
 impl<'a> FuzzyListState<'a> {
+    /// Empty state matching with `matcher` instead of the default skim matcher
+    pub fn with_matcher(matcher: Rc<dyn FuzzyMatcher>) -> Self {
+        let mut state = FuzzyListState::default();
+        state.set_matcher(matcher);
+        state
+    }
+
     pub fn with_items(items: Vec<FuzzyListItem<'a>>) -> Self {
         FuzzyListState {
-            offset: 0,
-            selected: None,
-            filter: None,
             items: Rc::new(items),
-            filtered: Rc::new(vec![]),
-            matcher: Rc::new(SkimMatcherV2::default()),
+            ..Default::default()
         }
     }
 
@@ -51,30 +333,171 @@ impl<'a> FuzzyListState<'a> {
         self.selected
     }
 
+    pub fn has_selection(&self) -> bool {
+        self.selected.is_some()
+    }
+
     pub fn select(&mut self, index: Option<usize>) {
+        if self.selected != index {
+            self.emit(StateEvent::SelectionChanged(index));
+        }
         self.selected = index;
+        self.detached = false;
         if index.is_none() {
             self.offset = 0;
         }
     }
 
-    pub fn increment_selected(&mut self) {
-        self.select(self.selected.map(|v| v + 1).or(Some(0)));
+    /// Scrolls the viewport by `delta` rows worth of items (e.g. for a mouse wheel) leaving the
+    /// selection untouched, even when it scrolls out of view. Selecting an item brings it back
+    pub fn scroll(&mut self, delta: i16) {
+        let max_offset = self.get_items().len().saturating_sub(1);
+        self.offset = (self.offset as i64 + delta as i64).clamp(0, max_offset as i64) as usize;
+        self.detached = true;
+        self.follow = false;
+    }
+
+    /// Scrolls the selected item back to the middle of the viewport after [`FuzzyListState::scroll`]
+    pub fn recenter_on_selection(&mut self) {
+        self.detached = false;
+        let items = self.get_items();
+        let selected = match self.selected {
+            Some(selected) if selected < items.len() => selected,
+            _ => return,
+        };
+        let mut above = self
+            .viewport_height
+            .saturating_sub(items[selected].height())
+            / 2;
+        let mut offset = selected;
+        while offset > 0 && items[offset - 1].height() <= above {
+            offset -= 1;
+            above -= items[offset].height();
+        }
+        self.offset = offset;
+    }
+
+    /// Handles an escape key press. With staged escape (the default) an active filter is cleared
+    /// first and only an escape without filter requests closing, otherwise closing is requested
+    /// immediately
+    pub fn escape(&mut self) -> NavOutcome {
+        if self.staged_escape && self.filter.is_some() {
+            self.set_filter(None);
+            NavOutcome::Cleared
+        } else {
+            NavOutcome::RequestClose
+        }
+    }
+
+    pub fn set_staged_escape(&mut self, staged_escape: bool) {
+        self.staged_escape = staged_escape;
+    }
+
+    /// Number of columns `next_focus` and `prev_focus` cycle through, typically the number of
+    /// columns passed to [`FuzzyList::columns`]
+    pub fn set_focus_columns(&mut self, columns: usize) {
+        self.focus_columns = columns;
+        self.focused_column = self.focused_column.min(columns.saturating_sub(1));
+    }
+
+    pub fn focused_column(&self) -> usize {
+        self.focused_column
+    }
+
+    /// Moves focus to the next column, wrapping around to the first one
+    pub fn next_focus(&mut self) {
+        if self.focus_columns > 0 {
+            self.focused_column = (self.focused_column + 1) % self.focus_columns;
+        }
+    }
+
+    /// Moves focus to the previous column, wrapping around to the last one
+    pub fn prev_focus(&mut self) {
+        if self.focus_columns > 0 {
+            self.focused_column =
+                (self.focused_column + self.focus_columns - 1) % self.focus_columns;
+        }
+    }
+
+    /// Deselects without touching the filter or the scroll offset, unlike `select(None)`
+    pub fn clear_selection(&mut self) {
+        if self.selected.is_some() {
+            self.emit(StateEvent::SelectionChanged(None));
+        }
+        self.selected = None;
+    }
+
+    pub fn increment_selected(&mut self) -> NavOutcome {
+        self.follow = false;
+        let items = self.get_items();
+        let start = self.selected.map(|v| v + 1).unwrap_or(0);
+        match (start..items.len()).find(|&i| items[i].is_selectable()) {
+            Some(next) => {
+                self.select(Some(next));
+                NavOutcome::Moved
+            }
+            None => NavOutcome::EscapeBottom,
+        }
+    }
+
+    pub fn decrement_selected(&mut self) -> NavOutcome {
+        self.follow = false;
+        let items = self.get_items();
+        let end = self.selected.unwrap_or(0).min(items.len());
+        match (0..end).rev().find(|&i| items[i].is_selectable()) {
+            Some(previous) => {
+                self.select(Some(previous));
+                NavOutcome::Moved
+            }
+            None => NavOutcome::EscapeTop,
+        }
+    }
+
+    pub fn set_on_confirm(&mut self, on_confirm: Option<Rc<dyn Fn(usize)>>) {
+        self.on_confirm = on_confirm;
     }
 
-    pub fn decrement_selected(&mut self) {
-        self.select(self.selected.map(|v| if v > 0 { v - 1 } else { v }));
+    /// Accepts the selected item, returning its original index and invoking the confirm callback
+    pub fn confirm(&mut self) -> Option<usize> {
+        let index = self.selected.and_then(|s| self.original_index(s))?;
+        if let Some(on_confirm) = &self.on_confirm {
+            on_confirm(index);
+        }
+        Some(index)
     }
 
     pub fn get_filter(&self) -> Option<String> {
         self.filter.clone()
     }
 
+    /// Filters the items. `Some("")`, e.g. a query whose characters were all deleted, and `None`,
+    /// filtering cancelled by the host, both show every item and are stored as no filter, but an
+    /// active filter becoming empty emits [`StateEvent::FilterEmptied`] while cancelling it emits
+    /// [`StateEvent::FilterCleared`]
+    ///
+    /// A `:` followed by a number, e.g. `:42`, does not filter but selects the item at that
+    /// 1-based position of the displayed items, keeping the current filter
     pub fn set_filter(&mut self, filter: Option<&str>) {
+        // an explicit filter supersedes any debounced one
+        self.pending_filter = None;
+        if let Some(position) = filter.and_then(jump_position) {
+            self.jump_to(position);
+            return;
+        }
+        if self.filter.is_some() {
+            match filter {
+                Some("") => self.emit(StateEvent::FilterEmptied),
+                None => self.emit(StateEvent::FilterCleared),
+                Some(_) => {}
+            }
+        }
         let filter = filter.filter(|f| !f.is_empty());
         let should_filter = match (filter, self.filter.clone()) {
             (None, Some(_)) => {
                 self.filtered = Rc::new(vec![]);
+                self.cached_total_height.set(None);
+                self.filtered_indices = Rc::new(vec![]);
+                self.filtered_scores = Rc::new(vec![]);
                 false
             }
             (Some(_), None) => true,
@@ -83,227 +506,2242 @@ impl<'a> FuzzyListState<'a> {
             _ => false,
         };
         if should_filter {
-            let len = self.items.len();
-            self.filtered = Rc::new(
-                (0..len)
-                    .map(|i| self.items[i].clone())
-                    .filter_map(|mut item| {
-                        if item.matches(&self.matcher, filter.unwrap()) {
-                            Some(item.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect(),
-            );
-            self.selected = None;
+            self.filter_items(filter.unwrap());
+        }
+        self.store_filter(filter);
+        if should_filter {
+            self.accept_unique_match();
         }
-        self.filter = filter
-            .map(|f| f.into())
-            .and_then(|f: String| if f.is_empty() { None } else { Some(f) });
     }
 
-    pub fn get_items(&self) -> Rc<Vec<FuzzyListItem<'a>>> {
-        if self.filtered.is_empty() {
-            self.items.clone()
+    /// Selects and confirms the unique match of a changed query when auto accept is on. Only
+    /// called for new queries so that refiltering refreshed items does not confirm again
+    fn accept_unique_match(&mut self) {
+        if let (true, Some(index)) = (self.auto_accept_single, self.unique_match) {
+            let position = self.filtered_indices.iter().position(|&i| i == index);
+            self.select(position);
+            self.confirm();
+        }
+    }
+
+    /// Selects the displayed item at 1-based `position`, the last one when past the end
+    fn jump_to(&mut self, position: usize) {
+        let len = self.effective_len();
+        if position == 0 || len == 0 {
+            return;
+        }
+        self.select(Some((position - 1).min(len - 1)));
+    }
+
+    fn store_filter(&mut self, filter: Option<&str>) {
+        let filter = filter.map(String::from);
+        if self.filter != filter {
+            self.emit(StateEvent::FilterChanged(filter.clone()));
+        }
+        self.filter = filter;
+    }
+
+    /// Filter as the user types. Below the minimum length set with `set_live_min_len` the filter
+    /// is cleared, and when the query extends the previous one only the current results are
+    /// matched again. Returns whether the displayed items changed.
+    ///
+    /// With a debounce set the query is only applied by `tick` once no other query was typed for
+    /// the debounce period, earlier queries are dropped and this returns false
+    pub fn set_filter_live(&mut self, filter: &str) -> bool {
+        if self.debounce.is_some() {
+            self.pending_filter = Some((filter.to_string(), Instant::now()));
+            return false;
+        }
+        self.apply_filter_live(filter)
+    }
+
+    fn apply_filter_live(&mut self, filter: &str) -> bool {
+        if let Some(position) = jump_position(filter) {
+            self.jump_to(position);
+            return false;
+        }
+        let before = self.shown_indices();
+        // a query below the minimum length counts as emptied, not as cancelled
+        let filter = if filter.chars().count() < self.live_min_len {
+            ""
         } else {
-            self.filtered.clone()
+            filter
+        };
+        match (filter, self.filter.clone()) {
+            (x, Some(y)) if x != y && x.starts_with(&y) => {
+                // a query extending the previous one can only match a subset of its results
+                let candidates = if self.match_count == 0 && !self.passthrough {
+                    vec![]
+                } else {
+                    self.filtered_indices.to_vec()
+                };
+                self.filter_candidates(x, candidates);
+                self.store_filter(Some(filter));
+                self.accept_unique_match();
+            }
+            _ => self.set_filter(Some(filter)),
         }
+        self.shown_indices() != before
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct FuzzyListItem<'a> {
-    content: Text<'a>,
-    style: Style,
-    filter_style: Style,
-}
+    /// Quiet period `set_filter_live` waits for before filtering, coalescing rapid typing. Pending
+    /// queries are applied by [`FuzzyListState::tick`]
+    pub fn set_debounce(&mut self, debounce: Option<Duration>) {
+        self.debounce = debounce;
+        if debounce.is_none() {
+            if let Some((filter, _)) = self.pending_filter.take() {
+                self.apply_filter_live(&filter);
+            }
+        }
+    }
 
-impl<'a> FuzzyListItem<'a> {
-    pub fn new<T>(content: T) -> FuzzyListItem<'a>
-    where
-        T: Into<Text<'a>>,
-    {
-        FuzzyListItem {
-            content: content.into(),
-            style: Style::default(),
-            filter_style: Style::default().fg(Color::Red),
+    /// Whether a live filter is waiting for the debounce period to pass
+    pub fn has_pending_filter(&self) -> bool {
+        self.pending_filter.is_some()
+    }
+
+    /// Minimum number of characters before `set_filter_live` starts filtering, shorter queries
+    /// show every item like an emptied query
+    pub fn set_live_min_len(&mut self, min_len: usize) {
+        self.live_min_len = min_len;
+    }
+
+    /// Display width of the widest item, e.g. to size a popup to its contents
+    pub fn max_content_width(&self) -> usize {
+        self.max_width_of(0..self.items.len())
+    }
+
+    /// Display width of the widest item currently shown, honouring the active filter
+    pub fn max_filtered_content_width(&self) -> usize {
+        self.max_width_of(self.shown_indices())
+    }
+
+    /// Plain text of the displayed items in display order, one line per content line and without
+    /// styles or highlights, e.g. to copy the narrowed results. Empty when the filter matches nothing
+    pub fn filtered_to_string(&self) -> String {
+        if self.filtered_out() {
+            return String::new();
         }
+        self.get_items()
+            .iter()
+            .flat_map(|item| item.content().lines.iter())
+            .map(|spans| {
+                spans
+                    .0
+                    .iter()
+                    .map(|s| s.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
-    pub fn style(mut self, style: Style) -> FuzzyListItem<'a> {
-        self.style = style;
-        self
+    /// Whether the selected item is taller than `viewport_height` rows, in which case it is
+    /// rendered clipped, e.g. to hint at the hidden lines or switch to a detail view
+    pub fn selected_clipped(&self, viewport_height: usize) -> bool {
+        let items = self.get_items();
+        self.selected
+            .and_then(|selected| items.get(selected))
+            .is_some_and(|item| item.height() > viewport_height)
     }
 
-    pub fn filter_style(mut self, filter_style: Style) -> FuzzyListItem<'a> {
-        self.filter_style = filter_style;
-        self
+    /// Scroll progress from 0.0 at the top to 1.0 once the last row is visible within
+    /// `viewport_height` rows, measured in rows so multi-line items count fully. Lists that fit
+    /// the viewport are at 0.0
+    pub fn scroll_fraction(&self, viewport_height: usize) -> f32 {
+        let items = self.get_items();
+        let scrollable = self.total_height().saturating_sub(viewport_height);
+        if scrollable == 0 {
+            return 0.0;
+        }
+        let above: usize = items.iter().take(self.offset).map(|i| i.height()).sum();
+        (above as f32 / scrollable as f32).min(1.0)
     }
 
-    pub fn height(&self) -> usize {
-        self.content.height()
+    /// Displayed position of the item drawn on screen row `row` by the last render, e.g. for mouse
+    /// hit testing
+    pub fn item_at(&self, row: u16) -> Option<usize> {
+        self.rendered_rows
+            .iter()
+            .find(|(rows, _)| rows.contains(&row))
+            .map(|&(_, index)| index)
     }
 
-    pub fn matches(&mut self, matcher: &Rc<dyn FuzzyMatcher>, filter: &str) -> bool {
-        let mut matches = false;
-        self.content.lines.iter_mut().for_each(|spans| {
-            let spans_cloned = spans.clone();
-            let filtered_spans: Vec<Span> = spans_cloned
-                .0
+    /// Scrolls to the items represented by the minimap cell at `column` and `row` of the last
+    /// render, e.g. for a mouse click. Returns whether the position was on the minimap
+    pub fn click_minimap(&mut self, column: u16, row: u16) -> bool {
+        let Some(area) = self.minimap_area else {
+            return false;
+        };
+        if column != area.x || row < area.top() || row >= area.bottom() {
+            return false;
+        }
+        match minimap_bucket(self.effective_len(), area.height, row - area.y) {
+            Some(bucket) => {
+                self.offset = bucket.start;
+                self.detached = true;
+                self.follow = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Action id of the item clicked on screen row `row` of the list rendered in `area`. Items
+    /// without an action return `None` and are selected as usual by the host
+    pub fn clicked_action_at(&self, area: Rect, row: u16) -> Option<u32> {
+        if row < area.top() || row >= area.bottom() {
+            return None;
+        }
+        let index = self.item_at(row)?;
+        self.get_items().get(index)?.click_action_id
+    }
+
+    /// Number of rows needed to render every displayed item, e.g. to size a popup to its contents
+    pub fn total_height(&self) -> usize {
+        if let Some(height) = self.cached_total_height.get() {
+            return height;
+        }
+        let height = self.get_items().iter().map(FuzzyListItem::height).sum();
+        self.cached_total_height.set(Some(height));
+        height
+    }
+
+    fn max_width_of(&self, indices: impl IntoIterator<Item = usize>) -> usize {
+        let mut widths = self.item_widths.borrow_mut();
+        let widths =
+            widths.get_or_insert_with(|| self.items.iter().map(FuzzyListItem::width).collect());
+        indices.into_iter().map(|i| widths[i]).max().unwrap_or(0)
+    }
+
+    /// Whether an active filter left no item, which `get_items` does not tell apart from no filter
+    fn filtered_out(&self) -> bool {
+        self.filter.is_some() && self.filtered.is_empty()
+    }
+
+    /// Original indices of the displayed items
+    fn shown_indices(&self) -> Vec<usize> {
+        (0..self.effective_len())
+            .filter_map(|i| self.original_index(i))
+            .collect()
+    }
+
+    /// Replaces all items and re-applies the active filter, keeping the item at the selected
+    /// original index selected while it is shown. With an identity key the selection follows the
+    /// selected item to its new position
+    pub fn set_items(&mut self, items: Vec<FuzzyListItem<'a>>) {
+        let selected_key = match (&self.identity_key, self.selected) {
+            (Some(key), Some(selected)) => self.get_items().get(selected).map(|item| key(item)),
+            _ => None,
+        };
+        let selected_original = self.selected.and_then(|s| self.original_index(s));
+        self.items = Rc::new(self.dedup(items));
+        self.item_widths.replace(None);
+        self.cached_total_height.set(None);
+        self.reset_matcher();
+        self.emit(StateEvent::ItemsChanged);
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+            self.reselect(selected_original);
+        } else if self.selected.is_some_and(|s| s >= self.items.len()) {
+            self.select(None);
+        }
+        if let (Some(key), Some(selected_key)) = (self.identity_key.clone(), selected_key) {
+            let items = self.get_items();
+            match items.iter().position(|item| key(item) == selected_key) {
+                Some(position) => self.select(Some(position)),
+                // the selected item is gone, do not leave the selection on whatever took its index
+                None if self.filter.is_none() => self.clear_selection(),
+                None => {}
+            }
+        }
+    }
+
+    /// Adds `items` after the current ones, re-applying the active filter. While following, the
+    /// last item gets selected
+    pub fn append_items(&mut self, items: Vec<FuzzyListItem<'a>>) {
+        let mut all = self.items.as_ref().clone();
+        all.extend(items);
+        self.set_items(all);
+        if self.follow {
+            self.select_last();
+        }
+    }
+
+    /// Keeps the last item selected as items are appended, like `tail -f`. Navigating or scrolling
+    /// stops following until it is enabled again
+    pub fn set_follow(&mut self, follow: bool) {
+        self.follow = follow;
+        if follow {
+            self.select_last();
+        }
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.follow
+    }
+
+    fn select_last(&mut self) {
+        let last = self
+            .get_items()
+            .iter()
+            .rposition(|item| item.is_selectable());
+        self.select(last);
+    }
+
+    /// Key identifying items across [`FuzzyListState::set_items`] calls, so that the selection
+    /// survives refreshes that reorder, add or remove items
+    pub fn set_identity_key(&mut self, key: Option<ItemKey<'a>>) {
+        self.identity_key = key;
+    }
+
+    /// Keeps only the first item for each key, for current items and whenever items are set
+    pub fn dedup_by_key(&mut self, key: ItemKey<'a>) {
+        self.dedup_key = Some(key);
+        let items = self.items.as_ref().clone();
+        self.set_items(items);
+    }
+
+    fn dedup(&self, items: Vec<FuzzyListItem<'a>>) -> Vec<FuzzyListItem<'a>> {
+        match &self.dedup_key {
+            Some(key) => {
+                let mut seen = HashSet::new();
+                items
+                    .into_iter()
+                    .filter(|item| seen.insert(key(item)))
+                    .collect()
+            }
+            None => items,
+        }
+    }
+
+    /// Replaces the item at `index` and re-applies the active filter so results reflect the new content,
+    /// keeping the selected item selected while it is shown.
+    /// The default skim matcher is recreated as well so that no cached state from the old content survives,
+    /// a matcher given to [`FuzzyListState::set_matcher`] is kept.
+    pub fn update_item(&mut self, index: usize, item: FuzzyListItem<'a>) {
+        let selected_original = self.selected.and_then(|s| self.original_index(s));
+        match Rc::make_mut(&mut self.items).get_mut(index) {
+            Some(slot) => *slot = item,
+            None => return,
+        }
+        self.item_widths.replace(None);
+        self.cached_total_height.set(None);
+        self.reset_matcher();
+        self.emit(StateEvent::ItemsChanged);
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+            self.reselect(selected_original);
+        }
+    }
+
+    /// Selects the item at original index `original` wherever refiltering placed it. Items no
+    /// longer shown leave the selection refiltering made
+    fn reselect(&mut self, original: Option<usize>) {
+        let Some(original) = original else {
+            return;
+        };
+        if let Some(position) = self.shown_indices().iter().position(|&i| i == original) {
+            self.select(Some(position));
+        }
+    }
+
+    fn filter_items(&mut self, filter: &str) {
+        let range = match &self.filter_range {
+            Some(range) => range.start.min(self.items.len())..range.end.min(self.items.len()),
+            None => 0..self.items.len(),
+        };
+        self.filter_candidates(filter, range.collect());
+    }
+
+    /// Restricts filtering to the items within `range`, items outside it are never matched
+    pub fn set_filter_range(&mut self, range: Option<Range<usize>>) {
+        self.filter_range = range;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Filters the items at the given original indices
+    fn filter_candidates(&mut self, filter: &str, candidates: Vec<usize>) {
+        self.emit(StateEvent::FilterStarted);
+        let started = Instant::now();
+        let scanned = candidates.len();
+        let mut match_count = 0;
+        let mut matched: BTreeMap<usize, (FuzzyListItem<'a>, Option<i64>)> = candidates
+            .into_iter()
+            .map(|i| (i, self.items[i].clone()))
+            .filter_map(|(i, mut item)| {
+                let score = self.match_item(&mut item, filter);
+                if score.is_some() {
+                    match_count += 1;
+                    Some((i, (item, score)))
+                } else if self.passthrough && !item.always_visible {
+                    Some((i, (item, None)))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        self.match_count = match_count;
+        self.unique_match = matched
+            .keys()
+            .next()
+            .copied()
+            .filter(|_| matched.len() == 1 && match_count == 1);
+        if self.keep_ancestors {
+            let ancestors: Vec<usize> = matched.keys().flat_map(|&i| self.ancestors(i)).collect();
+            for ancestor in ancestors {
+                matched
+                    .entry(ancestor)
+                    .or_insert_with(|| (self.items[ancestor].clone(), None));
+            }
+        }
+        let matched = self.order_filtered(matched);
+        let always_visible = || {
+            self.items
                 .iter()
-                .flat_map(|span| {
-                    let content = span.content.as_ref();
-                    let match_indices = matcher.fuzzy_indices(content, filter);
-                    if let Some(indices) = match_indices {
-                        matches = true;
-                        // dbg!(&indices);
-                        let index = *indices.1.first().unwrap();
-
-                        // consider only first match. split text into three or two partes
-                        if index > 0 && index < content.len() - filter.len() {
-                            vec![
-                                Span::raw(String::from(&content[0..index])),
-                                Span::styled(
-                                    String::from(&content[index..index + filter.len()]),
-                                    self.filter_style,
-                                ),
-                                Span::raw(String::from(&content[index + filter.len()..])),
-                            ]
-                        } else if index == 0 {
-                            vec![
-                                Span::styled(
-                                    String::from(&content[0..filter.len()]),
-                                    self.filter_style,
-                                ),
-                                Span::raw(String::from(&content[filter.len()..])),
-                            ]
-                        } else {
-                            vec![
-                                Span::raw(String::from(&content[0..content.len() - filter.len()])),
-                                Span::styled(
-                                    String::from(&content[content.len() - filter.len()..]),
-                                    self.filter_style,
-                                ),
-                            ]
-                        }
-                    } else {
-                        vec![Span::raw(String::from(content))]
-                    }
-                })
-                .collect();
-            *spans = Spans::from(filtered_spans);
+                .enumerate()
+                .filter(|(_, item)| item.always_visible)
+                .map(|(i, item)| (i, (item.clone(), None)))
+        };
+        let (indices, filtered): (Vec<usize>, Vec<_>) = match self.always_visible_position {
+            AlwaysVisiblePosition::Top => always_visible().chain(matched).unzip(),
+            AlwaysVisiblePosition::Bottom => matched.into_iter().chain(always_visible()).unzip(),
+        };
+        let (filtered, scores) = filtered.into_iter().unzip();
+        self.filtered = Rc::new(filtered);
+        self.cached_total_height.set(None);
+        self.filtered_indices = Rc::new(indices);
+        self.filtered_scores = Rc::new(scores);
+        self.filtered_at = self.ticks;
+        let duration = started.elapsed();
+        if self.collect_stats {
+            self.stats.filter_duration = duration;
+            self.stats.items_scanned = scanned;
+        }
+        self.emit(StateEvent::FilterCompleted {
+            matched: match_count,
+            duration,
         });
-        matches
+        if self.auto_select_top {
+            let top = self
+                .filtered
+                .iter()
+                .position(|item| item.is_selectable() && !item.always_visible);
+            self.select(top);
+            self.offset = 0;
+        } else {
+            self.clear_selection();
+        }
     }
-}
 
-/// A widget to display several items among which one can be selected (optional)
-/// Supports fuzzy filtering of content
-/// # Examples
-///
-/// ```
-/// # use tui::widgets::{Block, Borders, List, ListItem};
-/// # use tui::style::{Style, Color, Modifier};
-/// let items = [ListItem::new("Item 1"), ListItem::new("Item 2"), ListItem::new("Item 3")];
-/// List::new(items)
-///     .block(Block::default().title("List").borders(Borders::ALL))
-///     .style(Style::default().fg(Color::White))
-///     .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
-///     .highlight_symbol(">>");
-/// ```
-#[derive(Clone)]
-pub struct FuzzyList<'a> {
-    block: Option<Block<'a>>,
-    items: Rc<Vec<FuzzyListItem<'a>>>,
-    /// Style used as a base style for the widget
-    style: Style,
-    start_corner: Corner,
-    /// Style used to render selected item
-    highlight_style: Style,
-    /// Symbol in front of the selected item (Shift all items to the right)
-    highlight_symbol: Option<&'a str>,
-    /// Whether to repeat the highlight symbol for each line of the selected item
-    repeat_highlight_symbol: bool,
-}
+    /// Sorts filtered items by the order mode, then by the tiebreak key, with remaining ties kept
+    /// in original order. Passthrough keeps
+    /// the order of the host and kept ancestors stay directly above their descendants, ranked by
+    /// the best match below them
+    fn order_filtered(
+        &self,
+        matched: BTreeMap<usize, (FuzzyListItem<'a>, Option<i64>)>,
+    ) -> Vec<(usize, (FuzzyListItem<'a>, Option<i64>))> {
+        if self.passthrough || self.order_mode == OrderMode::Original {
+            return matched.into_iter().collect();
+        }
+        let mut best: BTreeMap<usize, Option<i64>> =
+            matched.iter().map(|(&i, &(_, score))| (i, score)).collect();
+        if self.keep_ancestors {
+            for (&i, &(_, score)) in matched.iter() {
+                for ancestor in self.ancestors(i) {
+                    if let Some(best) = best.get_mut(&ancestor) {
+                        *best = (*best).max(score);
+                    }
+                }
+            }
+        }
+        let texts: BTreeMap<usize, String> = match self.order_mode {
+            OrderMode::Alphabetical => matched
+                .iter()
+                .map(|(&i, (item, _))| (i, item.search_text()))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+        let tiebreaks: BTreeMap<usize, String> = match &self.tiebreak_key {
+            Some(key) => matched
+                .iter()
+                .map(|(&i, (item, _))| (i, key(item)))
+                .collect(),
+            None => BTreeMap::new(),
+        };
+        let compare = |a: &usize, b: &usize| {
+            let order = match self.order_mode {
+                OrderMode::MatchScore => best[b].cmp(&best[a]),
+                OrderMode::Alphabetical => texts[a].cmp(&texts[b]),
+                OrderMode::Original => Ordering::Equal,
+            };
+            order.then_with(|| tiebreaks.get(a).cmp(&tiebreaks.get(b)))
+        };
+        let mut order: Vec<usize> = matched.keys().copied().collect();
+        // stable sort, ties stay in original order
+        order.sort_by(compare);
+        if self.keep_ancestors {
+            order = self.ancestors_first(order);
+        }
+        let mut matched = matched;
+        order
+            .into_iter()
+            .filter_map(|i| matched.remove(&i).map(|entry| (i, entry)))
+            .collect()
+    }
+
+    /// Rearranges sorted original indices so that each item follows its closest listed ancestor,
+    /// keeping the sorted order among siblings
+    fn ancestors_first(&self, order: Vec<usize>) -> Vec<usize> {
+        let listed: HashSet<usize> = order.iter().copied().collect();
+        let mut children: BTreeMap<Option<usize>, Vec<usize>> = BTreeMap::new();
+        for &i in order.iter() {
+            let parent = self.ancestors(i).into_iter().find(|a| listed.contains(a));
+            children.entry(parent).or_default().push(i);
+        }
+        let mut arranged = Vec::with_capacity(order.len());
+        let mut stack: Vec<usize> = children.remove(&None).unwrap_or_default();
+        stack.reverse();
+        while let Some(i) = stack.pop() {
+            arranged.push(i);
+            if let Some(below) = children.remove(&Some(i)) {
+                stack.extend(below.into_iter().rev());
+            }
+        }
+        // items on a parent cycle are never reached from the top level
+        let placed: HashSet<usize> = arranged.iter().copied().collect();
+        arranged.extend(order.into_iter().filter(|i| !placed.contains(i)));
+        arranged
+    }
+
+    /// Whether the active filter narrowed the list down to exactly one selectable item.
+    /// Non-selectable items and ancestors kept as context are not counted, while items shown
+    /// without matching, e.g. in passthrough mode, rule out a unique match
+    pub fn is_unique_match(&self) -> bool {
+        self.filter.is_some() && self.unique_match.is_some()
+    }
+
+    /// Marks the item at original index `index` as the one confirmed last, rendered with
+    /// [`FuzzyList::last_confirmed_symbol`] whether or not a filter is active
+    pub fn set_last_confirmed(&mut self, index: Option<usize>) {
+        self.last_confirmed = index;
+    }
+
+    pub fn last_confirmed(&self) -> Option<usize> {
+        self.last_confirmed
+    }
+
+    /// Keeps every item when filtering, only highlighting those matching the filter. For data the
+    /// host already filtered and ranked, e.g. with an external search engine
+    pub fn set_passthrough(&mut self, passthrough: bool) {
+        self.passthrough = passthrough;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Selects the top result after each filter so that confirming accepts the best match right away
+    pub fn set_auto_select_top(&mut self, auto_select_top: bool) {
+        self.auto_select_top = auto_select_top;
+    }
+
+    /// Selects and confirms the remaining item whenever a new query leaves a unique match
+    pub fn set_auto_accept_single(&mut self, auto_accept_single: bool) {
+        self.auto_accept_single = auto_accept_single;
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    /// Sets the case sensitivity of matching and re-applies the active filter. Only the default
+    /// matcher is affected, a matcher given to [`FuzzyListState::set_matcher`] keeps its own settings
+    pub fn set_case_mode(&mut self, case_mode: CaseMode) {
+        self.case_mode = case_mode;
+        self.reset_matcher();
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Replaces the matcher, e.g. by a `SkimMatcherV2` configured differently or another
+    /// algorithm. When a filter is active it is re-applied so that the results match the new matcher
+    pub fn set_matcher(&mut self, matcher: Rc<dyn FuzzyMatcher>) {
+        self.matcher = matcher;
+        self.custom_matcher = true;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Recreates the default matcher for the current case mode, unless the host supplied its own
+    fn reset_matcher(&mut self) {
+        if !self.custom_matcher {
+            self.matcher = Rc::new(self.case_mode.matcher());
+        }
+    }
+
+    /// Cycles through the [`CaseMode`] variants, e.g. bound to a key with
+    /// [`FuzzyListState::case_mode`] rendered as an indicator
+    pub fn toggle_case_mode(&mut self) -> CaseMode {
+        self.set_case_mode(self.case_mode.next());
+        self.case_mode
+    }
+
+    /// Matches the text returned by `transform` for the content of each span instead of the content
+    /// itself, e.g. to strip markup, while rendering stays unchanged. Spans whose text is changed by
+    /// the transform are not highlighted as match positions no longer line up
+    pub fn set_match_transform(&mut self, transform: Option<MatchTransform>) {
+        self.match_transform = transform;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Text the matcher runs against for `item`, replacing search keys, romanization and the
+    /// match transform. When it differs from [`FuzzyListItem::search_text`] matches are not
+    /// highlighted, since their positions do not line up with the displayed content
+    pub fn set_haystack_fn(&mut self, haystack: Option<ItemKey<'a>>) {
+        self.haystack_fn = haystack;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Matches `filter` against `item` the way filtering does, honouring the haystack hook and
+    /// the match options, and highlights it. Returns the score of the match
+    fn match_item(&self, item: &mut FuzzyListItem<'a>, filter: &str) -> Option<i64> {
+        match &self.haystack_fn {
+            Some(haystack) => {
+                let haystack = haystack(item);
+                item.matches_haystack(&self.matcher, filter, &haystack, self.match_options())
+            }
+            None => item.matches_within(&self.matcher, filter, self.match_options()),
+        }
+    }
+
+    fn match_options(&self) -> MatchOptions<'_> {
+        MatchOptions {
+            max_gap: self.max_gap,
+            transform: self.match_transform.as_ref(),
+        }
+    }
+
+    /// Rejects matches skipping more than `max_gap` characters between two matched characters,
+    /// keeping typos within a word while dropping matches scattered across the whole item
+    pub fn set_max_gap(&mut self, max_gap: Option<usize>) {
+        self.max_gap = max_gap;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// How filtered items are ordered, e.g. [`OrderMode::Alphabetical`] for a stable order that does
+    /// not shift while typing
+    pub fn set_order_mode(&mut self, order_mode: OrderMode) {
+        self.order_mode = order_mode;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Whether the best matches are floated to the top while filtering, on by default. Turning it
+    /// off keeps the items in their original order
+    pub fn set_sort_by_score(&mut self, sort_by_score: bool) {
+        self.set_order_mode(if sort_by_score {
+            OrderMode::MatchScore
+        } else {
+            OrderMode::Original
+        });
+    }
+
+    /// Key ordering filtered items that the order mode ranks equally, e.g. the search text for an
+    /// alphabetical order among equal scores. Items with equal keys keep their original order
+    pub fn set_tiebreak_key(&mut self, key: Option<ItemKey<'a>>) {
+        self.tiebreak_key = key;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Where items marked with [`FuzzyListItem::always_visible`] are placed while filtering
+    pub fn set_always_visible_position(&mut self, position: AlwaysVisiblePosition) {
+        self.always_visible_position = position;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Whether ancestors of matching items are kept as context when filtering
+    pub fn set_keep_ancestors(&mut self, keep_ancestors: bool) {
+        self.keep_ancestors = keep_ancestors;
+        if let Some(filter) = self.filter.clone() {
+            self.filter_items(&filter);
+        }
+    }
+
+    /// Number of items matching the active filter, not counting ancestors kept as context
+    pub fn match_count(&self) -> usize {
+        if self.filter.is_some() {
+            self.match_count
+        } else {
+            self.items.len()
+        }
+    }
+
+    /// Original indices of the ancestors of the item at original `index`, nearest first
+    fn ancestors(&self, index: usize) -> Vec<usize> {
+        let parent = |i: usize| self.items.get(i).and_then(|item| item.parent);
+        iter::successors(parent(index), |&i| parent(i))
+            .take_while(|&i| i < self.items.len())
+            // guards against cycles in parent links
+            .take(self.items.len())
+            .collect()
+    }
+
+    /// Nesting depth of the item at original `index`
+    fn depth(&self, index: usize) -> usize {
+        self.ancestors(index).len()
+    }
+
+    /// Enables recording of [`Stats`] while filtering and rendering
+    pub fn set_collect_stats(&mut self, collect_stats: bool) {
+        self.collect_stats = collect_stats;
+    }
+
+    /// Measurements of the last filter and render, `None` unless collection is enabled
+    pub fn stats(&self) -> Option<Stats> {
+        self.collect_stats.then_some(self.stats)
+    }
+
+    /// Advances animations by one frame and applies a debounced live filter once its quiet
+    /// period passed
+    pub fn tick(&mut self) {
+        self.ticks = self.ticks.wrapping_add(1);
+        let quiet = match (&self.pending_filter, self.debounce) {
+            (Some((_, typed_at)), Some(debounce)) => typed_at.elapsed() >= debounce,
+            _ => false,
+        };
+        if quiet {
+            if let Some((filter, _)) = self.pending_filter.take() {
+                self.apply_filter_live(&filter);
+            }
+        }
+    }
+
+    /// Number of results revealed so far by the reveal animation, one more per tick since filtering
+    fn revealed(&self) -> usize {
+        self.ticks.wrapping_sub(self.filtered_at).saturating_add(1)
+    }
+
+    /// Starts or stops recording [`StateEvent`]s, off by default so that hosts which never take
+    /// them do not accumulate any. Stopping drops the events not yet taken
+    pub fn set_record_events(&mut self, record_events: bool) {
+        self.record_events = record_events;
+        if !record_events {
+            self.events.clear();
+        }
+    }
+
+    /// Drains the changes recorded since the last call. While recording, events accumulate until
+    /// taken
+    pub fn take_events(&mut self) -> Vec<StateEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn emit(&mut self, event: StateEvent) {
+        if self.record_events {
+            self.events.push(event);
+        }
+    }
+
+    pub fn get_items(&self) -> Rc<Vec<FuzzyListItem<'a>>> {
+        if self.filtered.is_empty() {
+            self.items.clone()
+        } else {
+            self.filtered.clone()
+        }
+    }
+
+    /// Content of the displayed item at `index` including the highlights of the current filter
+    pub fn rendered_item_text(&self, index: usize) -> Option<Text<'a>> {
+        self.get_items()
+            .get(index)
+            .map(|item| item.display_content(self.ticks).into_owned())
+    }
+
+    /// Selects the displayed item best matching `query` without filtering the list, and scrolls so
+    /// that it is visible within `viewport_height` rows. Returns the selected position
+    pub fn scroll_to_best_match(&mut self, query: &str, viewport_height: usize) -> Option<usize> {
+        let items = self.get_items();
+        let best = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.is_selectable() && !item.always_visible)
+            .filter_map(|(i, item)| {
+                self.match_item(&mut item.clone(), query)
+                    .map(|score| (i, score))
+            })
+            // first item wins ties
+            .fold(None, |best: Option<(usize, i64)>, (i, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((i, score)),
+            })
+            .map(|(i, _)| i)?;
+        if best < self.offset {
+            self.offset = best;
+        } else {
+            // advance the offset until the rows from it through the match fit the viewport
+            let mut height: usize = items[self.offset..=best].iter().map(|i| i.height()).sum();
+            while height > viewport_height && self.offset < best {
+                height -= items[self.offset].height();
+                self.offset += 1;
+            }
+        }
+        if self.selected != Some(best) {
+            self.emit(StateEvent::SelectionChanged(Some(best)));
+        }
+        self.selected = Some(best);
+        self.detached = false;
+        Some(best)
+    }
+
+    /// Positions of displayed items whose highlighted content differs from what `previous_filter`
+    /// would have produced, including items it did not match. Useful to redraw only changed rows
+    pub fn changed_since(&self, previous_filter: &str) -> Vec<usize> {
+        let items = self.get_items();
+        (0..items.len())
+            .filter(|&i| {
+                let Some(original) = self.original_index(i) else {
+                    return true;
+                };
+                let mut previous = self.items[original].clone();
+                let matched = previous_filter.is_empty()
+                    || self.match_item(&mut previous, previous_filter).is_some();
+                !matched || previous.highlighted != items[i].highlighted
+            })
+            .collect()
+    }
+
+    /// Number of items currently displayed, the filtered items when a filter matched anything
+    pub fn effective_len(&self) -> usize {
+        if self.filtered.is_empty() {
+            self.items.len()
+        } else {
+            self.filtered.len()
+        }
+    }
+
+    /// Maps an index of the displayed list back to the index of the item it was built from
+    pub fn original_index(&self, index: usize) -> Option<usize> {
+        if self.filtered.is_empty() {
+            (index < self.items.len()).then_some(index)
+        } else {
+            self.filtered_indices.get(index).copied()
+        }
+    }
+
+    /// Displayed items in display order, each with its original index and the score it matched
+    /// the active filter with. The score is `None` without filter and for items shown without
+    /// matching, such as kept ancestors and always visible items
+    pub fn matched_iter(
+        &self,
+    ) -> impl Iterator<Item = (usize, Option<i64>, &FuzzyListItem<'a>)> + '_ {
+        let items = if self.filtered.is_empty() {
+            &self.items
+        } else {
+            &self.filtered
+        };
+        items.iter().enumerate().map(move |(i, item)| {
+            let score = self.filtered_scores.get(i).copied().flatten();
+            (self.original_index(i).unwrap_or(i), score, item)
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct FuzzyListItem<'a> {
+    content: Text<'a>,
+    style: Style,
+    filter_style: Style,
+    /// Style used instead of `filter_style` when matched characters are contiguous
+    exact_match_style: Option<Style>,
+    selectable: bool,
+    /// Text matched instead of content for lazily generated items
+    search_key: Option<String>,
+    /// Generates the content of the item when it is rendered
+    generator: Option<Rc<dyn Fn() -> Text<'a> + 'a>>,
+    /// Index of the parent item for tree structured data
+    parent: Option<usize>,
+    /// Indentation depth, defaults to the depth in the parent hierarchy
+    indent: Option<usize>,
+    /// Cells rendered into the columns of the list
+    cells: Option<Vec<Text<'a>>>,
+    /// Content with the highlights of the last match
+    highlighted: Option<Text<'a>>,
+    /// Cells with the highlights of the last match
+    highlighted_cells: Option<Vec<Text<'a>>>,
+    /// Line and display column where each highlighted run of the content starts, in order
+    match_runs: Vec<(usize, u16)>,
+    /// Whether a spinner is shown instead of the content while it is being loaded
+    loading: bool,
+    highlight_granularity: HighlightGranularity,
+    /// Custom presentation of matches
+    highlight_renderer: Option<Rc<dyn HighlightRenderer>>,
+    /// Time shown in the relative time column, never matched
+    timestamp: Option<SystemTime>,
+    /// Whether the item is shown regardless of the filter
+    always_visible: bool,
+    /// Extra lines shown below the content while selected, see [`FuzzyList::expand_selected`]
+    expanded_content: Option<Text<'a>>,
+    /// Whether matched characters are underlined on top of their highlight style
+    underline_matches: bool,
+    /// Reading of the content (e.g. romaji or pinyin) matched alongside it
+    romanization: Option<String>,
+    /// Number of highlighted ranges per span above which the closest ones are merged
+    max_highlight_spans: Option<usize>,
+    /// Lines of the content rendered as context around the matching lines
+    context_lines: Vec<usize>,
+    /// Translates character indices of the search key to character indices of the content
+    search_key_map: Option<SearchKeyMap>,
+    /// Action triggered by clicking the item instead of selecting it
+    click_action_id: Option<u32>,
+}
+
+impl<'a> fmt::Debug for FuzzyListItem<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FuzzyListItem")
+            .field("content", &self.content)
+            .field("style", &self.style)
+            .field("filter_style", &self.filter_style)
+            .field("exact_match_style", &self.exact_match_style)
+            .field("selectable", &self.selectable)
+            .field("search_key", &self.search_key)
+            .field("lazy", &self.generator.is_some())
+            .field("parent", &self.parent)
+            .field("indent", &self.indent)
+            .field("cells", &self.cells)
+            .field("highlighted", &self.highlighted)
+            .field("highlighted_cells", &self.highlighted_cells)
+            .field("match_runs", &self.match_runs)
+            .field("loading", &self.loading)
+            .field("highlight_granularity", &self.highlight_granularity)
+            .field("highlight_renderer", &self.highlight_renderer.is_some())
+            .field("timestamp", &self.timestamp)
+            .field("always_visible", &self.always_visible)
+            .field("expanded_content", &self.expanded_content)
+            .field("underline_matches", &self.underline_matches)
+            .field("romanization", &self.romanization)
+            .field("max_highlight_spans", &self.max_highlight_spans)
+            .field("context_lines", &self.context_lines)
+            .field("search_key_map", &self.search_key_map.is_some())
+            .field("click_action_id", &self.click_action_id)
+            .finish()
+    }
+}
+
+impl<'a> PartialEq for FuzzyListItem<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        let same_generator = match (&self.generator, &other.generator) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        let same_renderer = match (&self.highlight_renderer, &other.highlight_renderer) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        let same_key_map = match (&self.search_key_map, &other.search_key_map) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        self.content == other.content
+            && self.style == other.style
+            && self.filter_style == other.filter_style
+            && self.exact_match_style == other.exact_match_style
+            && self.selectable == other.selectable
+            && self.search_key == other.search_key
+            && self.parent == other.parent
+            && self.indent == other.indent
+            && self.cells == other.cells
+            && self.highlighted == other.highlighted
+            && self.highlighted_cells == other.highlighted_cells
+            && self.match_runs == other.match_runs
+            && self.loading == other.loading
+            && self.highlight_granularity == other.highlight_granularity
+            && same_generator
+            && same_renderer
+            && self.timestamp == other.timestamp
+            && self.always_visible == other.always_visible
+            && self.expanded_content == other.expanded_content
+            && self.underline_matches == other.underline_matches
+            && self.romanization == other.romanization
+            && self.max_highlight_spans == other.max_highlight_spans
+            && self.context_lines == other.context_lines
+            && same_key_map
+            && self.click_action_id == other.click_action_id
+    }
+}
+
+impl<'a> Eq for FuzzyListItem<'a> {}
+
+impl<'a> FuzzyListItem<'a> {
+    pub fn new<T>(content: T) -> FuzzyListItem<'a>
+    where
+        T: Into<Text<'a>>,
+    {
+        FuzzyListItem {
+            content: content.into(),
+            style: Style::default(),
+            filter_style: FuzzyListItem::default_filter_style(),
+            exact_match_style: None,
+            selectable: true,
+            search_key: None,
+            generator: None,
+            parent: None,
+            indent: None,
+            cells: None,
+            highlighted: None,
+            highlighted_cells: None,
+            match_runs: vec![],
+            loading: false,
+            highlight_granularity: HighlightGranularity::Char,
+            highlight_renderer: None,
+            timestamp: None,
+            always_visible: false,
+            expanded_content: None,
+            underline_matches: false,
+            romanization: None,
+            max_highlight_spans: None,
+            context_lines: vec![],
+            search_key_map: None,
+            click_action_id: None,
+        }
+    }
+
+    /// Item laid out into the columns configured with [`FuzzyList::columns`], filters match every cell
+    pub fn cells(cells: Vec<Text<'a>>) -> FuzzyListItem<'a> {
+        let height = cells.iter().map(Text::height).max().unwrap_or(1);
+        FuzzyListItem {
+            cells: Some(cells),
+            ..FuzzyListItem::new(vec![Spans::default(); height])
+        }
+    }
+
+    /// Item whose content is only generated when it becomes visible.
+    /// `search_key` is matched against filters (without highlighting) and `height` is used for layout
+    pub fn lazy<T>(search_key: T, height: usize, generator: Rc<dyn Fn() -> Text<'a> + 'a>) -> Self
+    where
+        T: Into<String>,
+    {
+        FuzzyListItem {
+            selectable: true,
+            search_key: Some(search_key.into()),
+            generator: Some(generator),
+            ..FuzzyListItem::spacer(height)
+        }
+    }
+
+    /// Item highlighting matches with [`FuzzyListItem::background_filter_style`], which stays
+    /// legible on themes where red text is hard to tell apart
+    pub fn with_background_highlight<T>(content: T) -> FuzzyListItem<'a>
+    where
+        T: Into<Text<'a>>,
+    {
+        FuzzyListItem::new(content).filter_style(FuzzyListItem::background_filter_style())
+    }
+
+    /// Style of matched characters used by [`FuzzyListItem::new`], red foreground
+    pub fn default_filter_style() -> Style {
+        Style::default().fg(Color::Red)
+    }
+
+    /// Background based style of matched characters, black on yellow, readable on light and dark terminals
+    pub fn background_filter_style() -> Style {
+        Style::default().fg(Color::Black).bg(Color::Yellow)
+    }
+
+    /// Single line item rendered with `style` as its base style
+    pub fn styled<T>(content: T, style: Style) -> FuzzyListItem<'a>
+    where
+        T: Into<String>,
+    {
+        FuzzyListItem::new(content.into()).style(style)
+    }
+
+    /// Blank item spanning `height` rows, it is never selected nor matched by a filter
+    pub fn spacer(height: usize) -> FuzzyListItem<'a> {
+        FuzzyListItem {
+            selectable: false,
+            ..FuzzyListItem::new(vec![Spans::default(); height])
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> FuzzyListItem<'a> {
+        self.style = style;
+        self
+    }
+
+    pub fn filter_style(mut self, filter_style: Style) -> FuzzyListItem<'a> {
+        self.filter_style = filter_style;
+        self
+    }
+
+    pub fn exact_match_style(mut self, exact_match_style: Style) -> FuzzyListItem<'a> {
+        self.exact_match_style = Some(exact_match_style);
+        self
+    }
+
+    /// Links the item to the item at `parent` index, making it a child in tree structured data
+    pub fn parent(mut self, parent: Option<usize>) -> FuzzyListItem<'a> {
+        self.parent = parent;
+        self
+    }
+
+    /// Renders the item `depth` indentation levels deep, matching still ignores the indentation
+    pub fn indent(mut self, depth: usize) -> FuzzyListItem<'a> {
+        self.indent = Some(depth);
+        self
+    }
+
+    /// Shows an animated spinner, advanced by [`FuzzyListState::tick`], in place of the content
+    pub fn loading(mut self, loading: bool) -> FuzzyListItem<'a> {
+        self.loading = loading;
+        self
+    }
+
+    /// Content as given, without the highlights of any filter
+    pub fn content(&self) -> &Text<'a> {
+        &self.content
+    }
+
+    /// Reading of CJK content, e.g. romaji or pinyin computed by the host, so that typing the
+    /// reading matches the item. Matches found only in the reading are not highlighted
+    pub fn romanization<T>(mut self, romanization: T) -> FuzzyListItem<'a>
+    where
+        T: Into<String>,
+    {
+        self.romanization = Some(romanization.into());
+        self
+    }
+
+    /// Matches filters against `search_key` instead of the content, matches are then only
+    /// highlighted with a [`FuzzyListItem::search_key_map`]
+    pub fn search_key<T>(mut self, search_key: T) -> FuzzyListItem<'a>
+    where
+        T: Into<String>,
+    {
+        self.search_key = Some(search_key.into());
+        self
+    }
+
+    /// Maps character indices of the search key to character indices of the content (lines joined
+    /// by new lines), so that items with a derived search key, e.g. lowercased, are still
+    /// highlighted. Without it matches on a search key are not highlighted
+    pub fn search_key_map(mut self, map: SearchKeyMap) -> FuzzyListItem<'a> {
+        self.search_key_map = Some(map);
+        self
+    }
+
+    /// Makes clicking the item trigger the action `id`, reported by
+    /// [`FuzzyListState::clicked_action_at`], e.g. for inline buttons
+    pub fn click_action_id(mut self, id: Option<u32>) -> FuzzyListItem<'a> {
+        self.click_action_id = id;
+        self
+    }
+
+    /// Marks lines of the content, by index, as context around the matching lines, e.g. for grep
+    /// results. They are rendered with [`FuzzyList::context_style`], dimmed by default
+    pub fn context_lines(mut self, context_lines: Vec<usize>) -> FuzzyListItem<'a> {
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// Caps the number of highlighted ranges per span, merging the closest ones (and the characters
+    /// between them) beyond it. Trades exact highlighting for fewer spans on long items
+    pub fn max_highlight_spans(mut self, max_highlight_spans: usize) -> FuzzyListItem<'a> {
+        self.max_highlight_spans = Some(max_highlight_spans);
+        self
+    }
+
+    /// Underlines matched characters in addition to their highlight style, readable on monochrome
+    /// or low contrast terminals
+    pub fn underline_matches(mut self, underline_matches: bool) -> FuzzyListItem<'a> {
+        self.underline_matches = underline_matches;
+        self
+    }
+
+    pub fn highlight_granularity(mut self, granularity: HighlightGranularity) -> FuzzyListItem<'a> {
+        self.highlight_granularity = granularity;
+        self
+    }
+
+    pub fn highlight_renderer(mut self, renderer: Rc<dyn HighlightRenderer>) -> FuzzyListItem<'a> {
+        self.highlight_renderer = Some(renderer);
+        self
+    }
+
+    /// Time rendered by [`FuzzyList::relative_time`], it does not take part in matching
+    pub fn timestamp(mut self, timestamp: SystemTime) -> FuzzyListItem<'a> {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Shows the item whatever the filter, e.g. a "create new…" action. It is never matched and
+    /// is placed according to [`FuzzyListState::set_always_visible_position`] while filtering
+    pub fn always_visible(mut self, always_visible: bool) -> FuzzyListItem<'a> {
+        self.always_visible = always_visible;
+        self
+    }
+
+    /// Detail lines rendered below the content while the item is selected and
+    /// [`FuzzyList::expand_selected`] is enabled, they are not matched
+    pub fn expanded_content<T>(mut self, expanded_content: T) -> FuzzyListItem<'a>
+    where
+        T: Into<Text<'a>>,
+    {
+        self.expanded_content = Some(expanded_content.into());
+        self
+    }
+
+    pub fn height(&self) -> usize {
+        self.content.height()
+    }
+
+    /// Display width of the widest line, cells count with the space separating columns
+    fn width(&self) -> usize {
+        let cells = self.cells.as_ref().map(|cells| {
+            let widths: usize = cells.iter().map(Text::width).sum();
+            widths + cells.len().saturating_sub(1)
+        });
+        self.content.width().max(cells.unwrap_or(0))
+    }
+
+    pub fn is_selectable(&self) -> bool {
+        self.selectable
+    }
+
+    /// Plain text matched by filters by default: the search key when set, otherwise the lines of
+    /// the cells and content joined by new lines
+    pub fn search_text(&self) -> String {
+        if let Some(search_key) = &self.search_key {
+            return search_key.clone();
+        }
+        let lines = self.cells.iter().flatten().chain(iter::once(&self.content));
+        lines
+            .flat_map(|text| text.lines.iter())
+            .map(|spans| {
+                spans
+                    .0
+                    .iter()
+                    .map(|s| s.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Content as it should be displayed at animation frame `tick`, generating it for lazy items
+    fn display_content(&self, tick: usize) -> Cow<'_, Text<'a>> {
+        match &self.generator {
+            _ if self.loading => Cow::Owned(Text::raw(SPINNER_FRAMES[tick % SPINNER_FRAMES.len()])),
+            Some(generator) => Cow::Owned(generator()),
+            None => Cow::Borrowed(self.highlighted.as_ref().unwrap_or(&self.content)),
+        }
+    }
+
+    pub fn matches(&mut self, matcher: &Rc<dyn FuzzyMatcher>, filter: &str) -> bool {
+        self.matches_within(matcher, filter, MatchOptions::default())
+            .is_some()
+    }
+
+    /// Like [`FuzzyListItem::matches`], applying the constraints and transform of `options`.
+    /// Returns the best score of the texts that matched
+    fn matches_within(
+        &mut self,
+        matcher: &Rc<dyn FuzzyMatcher>,
+        filter: &str,
+        options: MatchOptions,
+    ) -> Option<i64> {
+        if !self.selectable || self.always_visible {
+            return None;
+        }
+        let romanization_score = || {
+            let romanization = self.romanization.as_ref()?;
+            fuzzy_indices(matcher, romanization, filter, options.max_gap).map(|(score, _)| score)
+        };
+        if let Some(search_key) = &self.search_key {
+            let key_match = fuzzy_indices(matcher, search_key, filter, options.max_gap);
+            if let (Some((_, indices)), Some(map)) = (&key_match, &self.search_key_map) {
+                let mut indices: Vec<usize> = indices.iter().filter_map(|&i| map(i)).collect();
+                indices.sort_unstable();
+                indices.dedup();
+                let mut runs = vec![];
+                self.highlighted = Some(self.highlight_indices(&indices, &mut runs));
+                self.match_runs = runs;
+            }
+            return key_match.map(|(score, _)| score).max(romanization_score());
+        }
+        // highlights are kept apart so that the content stays pristine
+        let mut content = self.content.clone();
+        let mut runs = vec![];
+        let mut score = self.highlight_text(&mut content, matcher, filter, options, &mut runs);
+        let cells = self.cells.clone().map(|mut cells| {
+            for cell in cells.iter_mut() {
+                let cell_score = self.highlight_text(cell, matcher, filter, options, &mut vec![]);
+                score = score.max(cell_score);
+            }
+            cells
+        });
+        self.highlighted = Some(content).filter(|_| score.is_some());
+        self.highlighted_cells = cells.filter(|_| score.is_some());
+        self.match_runs = runs;
+        // a match on the reading has no position in the content to highlight
+        score.max(romanization_score())
+    }
+
+    /// Matches `filter` against `haystack` instead of the content, returning the score of the
+    /// haystack. Highlights are only computed when the haystack is the displayed text
+    fn matches_haystack(
+        &mut self,
+        matcher: &Rc<dyn FuzzyMatcher>,
+        filter: &str,
+        haystack: &str,
+        options: MatchOptions,
+    ) -> Option<i64> {
+        if !self.selectable || self.always_visible {
+            return None;
+        }
+        let score = fuzzy_indices(matcher, haystack, filter, options.max_gap).map(|(s, _)| s);
+        if score.is_some() && self.search_key.is_none() && haystack == self.search_text() {
+            self.matches_within(matcher, filter, options);
+        }
+        score
+    }
+
+    /// Style of the characters matched at `indices`
+    fn match_style(&self, indices: &[usize]) -> Style {
+        let exact = indices.windows(2).all(|w| w[1] == w[0] + 1);
+        let style = match self.exact_match_style {
+            Some(style) if exact => style,
+            _ => self.filter_style,
+        };
+        if self.underline_matches {
+            style.add_modifier(Modifier::UNDERLINED)
+        } else {
+            style
+        }
+    }
+
+    /// Content with the characters at the given sorted `indices` highlighted, indices run over
+    /// the lines of the content as if joined by new lines. The start of each highlighted run is
+    /// added to `runs`
+    fn highlight_indices(&self, indices: &[usize], runs: &mut Vec<(usize, u16)>) -> Text<'a> {
+        let style = self.match_style(indices);
+        let mut text = self.content.clone();
+        let mut offset = 0;
+        for (line, spans) in text.lines.iter_mut().enumerate() {
+            let mut highlighted = vec![];
+            let mut column = 0;
+            for span in spans.0.drain(..) {
+                let len = span.content.chars().count();
+                let local: Vec<usize> = indices
+                    .iter()
+                    .filter(|&&i| i >= offset && i < offset + len)
+                    .map(|&i| i - offset)
+                    .collect();
+                let mut ranges = index_runs(&local);
+                if let Some(max_spans) = self.max_highlight_spans {
+                    ranges = merge_closest_ranges(ranges, max_spans);
+                }
+                runs.extend(
+                    ranges
+                        .iter()
+                        .map(|range| (line, column + char_column(&span.content, range.start))),
+                );
+                column += span.width() as u16;
+                if ranges.is_empty() {
+                    highlighted.push(span);
+                } else {
+                    highlighted.extend(highlight_ranges(&span.content, &ranges, style));
+                }
+                offset += len;
+            }
+            spans.0 = highlighted;
+            // the new line separating lines
+            offset += 1;
+        }
+        text
+    }
+
+    /// Highlights the first fuzzy match of `filter` in each span of `text`, returning the best
+    /// score of the spans. The start of each highlighted run is added to `runs`
+    fn highlight_text(
+        &self,
+        text: &mut Text<'a>,
+        matcher: &Rc<dyn FuzzyMatcher>,
+        filter: &str,
+        options: MatchOptions,
+        runs: &mut Vec<(usize, u16)>,
+    ) -> Option<i64> {
+        let mut score = None;
+        text.lines.iter_mut().enumerate().for_each(|(line, spans)| {
+            let spans_cloned = spans.clone();
+            let mut column = 0;
+            let filtered_spans: Vec<Span> = spans_cloned
+                .0
+                .iter()
+                .flat_map(|span| {
+                    let content = span.content.as_ref();
+                    let span_column = column;
+                    column += span.width() as u16;
+                    let mut push_runs =
+                        |ranges: &[Range<usize>]| {
+                            runs.extend(ranges.iter().map(|range| {
+                                (line, span_column + char_column(content, range.start))
+                            }));
+                        };
+                    let transformed = options.transform.map(|transform| transform(content));
+                    let haystack = transformed.as_deref().unwrap_or(content);
+                    let match_indices = fuzzy_indices(matcher, haystack, filter, options.max_gap);
+                    if let Some((span_score, indices)) = match_indices {
+                        score = score.max(Some(span_score));
+                        // indices point into the transformed text, they no longer line up with the content
+                        if haystack != content {
+                            return vec![Span::raw(String::from(content))];
+                        }
+                        if let Some(renderer) = &self.highlight_renderer {
+                            push_runs(&index_runs(&indices));
+                            return renderer.render_highlight(content, &indices);
+                        }
+                        let style = self.match_style(&indices);
+                        let mut ranges = index_runs(&indices);
+                        if self.highlight_granularity == HighlightGranularity::Word {
+                            ranges = expand_to_words(content, ranges);
+                        }
+                        if let Some(max_spans) = self.max_highlight_spans {
+                            ranges = merge_closest_ranges(ranges, max_spans);
+                        }
+                        push_runs(&ranges);
+                        highlight_ranges(content, &ranges, style)
+                    } else {
+                        vec![Span::raw(String::from(content))]
+                    }
+                })
+                .collect();
+            *spans = Spans::from(filtered_spans);
+        });
+        score
+    }
+}
+
+impl<'a, T> From<(T, Style)> for FuzzyListItem<'a>
+where
+    T: Into<String>,
+{
+    fn from((content, style): (T, Style)) -> Self {
+        FuzzyListItem::styled(content, style)
+    }
+}
+
+impl<'a, T> FromIterator<(T, Style)> for FuzzyListState<'a>
+where
+    T: Into<String>,
+{
+    fn from_iter<I: IntoIterator<Item = (T, Style)>>(iter: I) -> Self {
+        FuzzyListState::with_items(iter.into_iter().map(FuzzyListItem::from).collect())
+    }
+}
+
+/// Position of a `:N` jump query, other queries including non-numeric `:` ones are matched
+fn jump_position(filter: &str) -> Option<usize> {
+    filter.strip_prefix(':')?.parse().ok()
+}
+
+/// Indices of the fuzzy match of `filter` in `content`, rejected when two consecutive matched
+/// characters are more than `max_gap` characters apart
+fn fuzzy_indices(
+    matcher: &Rc<dyn FuzzyMatcher>,
+    content: &str,
+    filter: &str,
+    max_gap: Option<usize>,
+) -> Option<(i64, Vec<usize>)> {
+    matcher
+        .fuzzy_indices(content, filter)
+        .filter(|(_, indices)| match max_gap {
+            Some(max_gap) => indices.windows(2).all(|w| w[1] - w[0] - 1 <= max_gap),
+            None => true,
+        })
+}
+
+/// Displayed items represented by row `row` of a minimap `rows` high, one item per row when
+/// they all fit
+fn minimap_bucket(len: usize, rows: u16, row: u16) -> Option<Range<usize>> {
+    let (rows, row) = (rows as usize, row as usize);
+    if len <= rows {
+        return (row < len).then_some(row..row + 1);
+    }
+    Some(row * len / rows..(row + 1) * len / rows)
+}
+
+/// Label of the `n`th highlighted run, 1 to 9 then a to z
+fn match_label(n: usize) -> Option<char> {
+    let labels = "123456789abcdefghijklmnopqrstuvwxyz";
+    labels.chars().nth(n)
+}
+
+/// Display column of the character at `index` of `content`
+fn char_column(content: &str, index: usize) -> u16 {
+    let end = content
+        .char_indices()
+        .nth(index)
+        .map_or(content.len(), |(i, _)| i);
+    content[..end].width() as u16
+}
+
+/// Groups sorted character indices into ranges of consecutive characters
+fn index_runs(indices: &[usize]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = vec![];
+    for &index in indices {
+        match runs.last_mut() {
+            Some(run) if run.end == index => run.end += 1,
+            _ => runs.push(index..index + 1),
+        }
+    }
+    runs
+}
+
+/// Widens character ranges to the bounds of the words they start and end in
+fn expand_to_words(content: &str, ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut start = 0;
+    let words: Vec<Range<usize>> = content
+        .split_word_bounds()
+        .map(|word| {
+            let end = start + word.chars().count();
+            let range = start..end;
+            start = end;
+            range
+        })
+        .collect();
+    let word_of = |index: usize| words.iter().find(|word| word.contains(&index));
+    ranges
+        .into_iter()
+        .map(|range| {
+            let start = word_of(range.start).map_or(range.start, |word| word.start);
+            let end = word_of(range.end.saturating_sub(1)).map_or(range.end, |word| word.end);
+            start..end.max(range.end)
+        })
+        .collect()
+}
+
+/// Merges the sorted `ranges` separated by the smallest gaps, along with the characters between
+/// them, until at most `max` ranges remain
+fn merge_closest_ranges(mut ranges: Vec<Range<usize>>, max: usize) -> Vec<Range<usize>> {
+    while ranges.len() > max.max(1) {
+        let closest = (1..ranges.len())
+            .min_by_key(|&i| ranges[i].start.saturating_sub(ranges[i - 1].end))
+            .unwrap_or(1);
+        let merged = ranges.remove(closest);
+        ranges[closest - 1].end = ranges[closest - 1].end.max(merged.end);
+    }
+    ranges
+}
+
+/// Splits `content` into raw and `style`d spans, highlighting the given sorted character ranges.
+/// Range bounds are widened to grapheme cluster boundaries so that clusters are never split apart
+fn highlight_ranges<'b>(content: &str, ranges: &[Range<usize>], style: Style) -> Vec<Span<'b>> {
+    // byte offset of each character plus the end of the content
+    let offsets: Vec<usize> = content
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(iter::once(content.len()))
+        .collect();
+    let boundaries: Vec<usize> = content
+        .grapheme_indices(true)
+        .map(|(i, _)| i)
+        .chain(iter::once(content.len()))
+        .collect();
+    let to_byte = |index: usize| offsets[index.min(offsets.len() - 1)];
+
+    let mut spans = vec![];
+    let mut last = 0;
+    for range in ranges {
+        let (start, end) = (to_byte(range.start), to_byte(range.end));
+        let start = boundaries
+            .iter()
+            .rev()
+            .find(|&&b| b <= start)
+            .copied()
+            .unwrap_or(0)
+            .max(last);
+        let end = boundaries
+            .iter()
+            .find(|&&b| b >= end)
+            .copied()
+            .unwrap_or(content.len());
+        if start >= end {
+            continue;
+        }
+        if start > last {
+            spans.push(Span::raw(String::from(&content[last..start])));
+        }
+        spans.push(Span::styled(String::from(&content[start..end]), style));
+        last = end;
+    }
+    if last < content.len() {
+        spans.push(Span::raw(String::from(&content[last..])));
+    }
+    spans
+}
+
+/// Formats the time between `timestamp` and `now` in its largest unit, e.g. "2m ago" or "in 3d"
+fn format_relative_time(timestamp: SystemTime, now: SystemTime) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+    let (seconds, future) = match now.duration_since(timestamp) {
+        Ok(elapsed) => (elapsed.as_secs(), false),
+        Err(e) => (e.duration().as_secs(), true),
+    };
+    let (value, unit) = match seconds {
+        s if s < MINUTE => (s, "s"),
+        s if s < HOUR => (s / MINUTE, "m"),
+        s if s < DAY => (s / HOUR, "h"),
+        s if s < MONTH => (s / DAY, "d"),
+        s if s < YEAR => (s / MONTH, "mo"),
+        s => (s / YEAR, "y"),
+    };
+    if future {
+        format!("in {}{}", value, unit)
+    } else {
+        format!("{}{} ago", value, unit)
+    }
+}
+
+/// Replaces whitespace at the start and end of `line` (and between words when `internal` is set)
+/// with a visible glyph drawn in `style`
+fn visible_whitespace<'b>(line: &Spans<'b>, internal: bool, style: Style) -> Spans<'b> {
+    let chars: Vec<char> = line.0.iter().flat_map(|s| s.content.chars()).collect();
+    let first = chars
+        .iter()
+        .position(|c| !c.is_whitespace())
+        .unwrap_or(chars.len());
+    let last = chars.iter().rposition(|c| !c.is_whitespace()).unwrap_or(0);
+    let visible = |i: usize| internal || i < first || i > last;
+
+    let mut spans = vec![];
+    let mut index = 0;
+    for span in line.0.iter() {
+        let run_style = |whitespace: bool| {
+            if whitespace {
+                span.style.patch(style)
+            } else {
+                span.style
+            }
+        };
+        let mut run = String::new();
+        let mut run_is_whitespace = false;
+        for c in span.content.chars() {
+            let is_whitespace = c.is_whitespace() && visible(index);
+            if is_whitespace != run_is_whitespace && !run.is_empty() {
+                spans.push(Span::styled(
+                    mem::take(&mut run),
+                    run_style(run_is_whitespace),
+                ));
+            }
+            run_is_whitespace = is_whitespace;
+            run.push(if is_whitespace { '·' } else { c });
+            index += 1;
+        }
+        if !run.is_empty() {
+            spans.push(Span::styled(run, run_style(run_is_whitespace)));
+        }
+    }
+    Spans::from(spans)
+}
+
+/// Splits the first row off `area` when `split` is set and rows remain below it
+fn split_top_row(area: Rect, split: bool) -> (Option<Rect>, Rect) {
+    if split && area.height > 1 {
+        (
+            Some(Rect { height: 1, ..area }),
+            Rect {
+                y: area.y + 1,
+                height: area.height - 1,
+                ..area
+            },
+        )
+    } else {
+        (None, area)
+    }
+}
+
+/// Splits the last row off `area` when `split` is set and rows remain above it
+fn split_bottom_row(area: Rect, split: bool) -> (Rect, Option<Rect>) {
+    if split && area.height > 1 {
+        (
+            Rect {
+                height: area.height - 1,
+                ..area
+            },
+            Some(Rect {
+                y: area.bottom() - 1,
+                height: 1,
+                ..area
+            }),
+        )
+    } else {
+        (area, None)
+    }
+}
+
+/// Draws `line` within `area` aligned horizontally according to `alignment`
+fn set_aligned_spans(buf: &mut Buffer, area: Rect, line: &Spans, alignment: Alignment) {
+    if area.width == 0 {
+        return;
+    }
+    let free = area.width.saturating_sub(line.width() as u16);
+    let x = match alignment {
+        Alignment::Left => area.x,
+        Alignment::Center => area.x + free / 2,
+        Alignment::Right => area.x + free,
+    };
+    buf.set_spans(x, area.y, line, area.right() - x);
+}
+
+/// A widget to display several items among which one can be selected (optional)
+/// Supports fuzzy filtering of content
+/// # Examples
+///
+/// ```
+/// # use tui::widgets::{Block, Borders, List, ListItem};
+/// # use tui::style::{Style, Color, Modifier};
+/// let items = [ListItem::new("Item 1"), ListItem::new("Item 2"), ListItem::new("Item 3")];
+/// List::new(items)
+///     .block(Block::default().title("List").borders(Borders::ALL))
+///     .style(Style::default().fg(Color::White))
+///     .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+///     .highlight_symbol(">>");
+/// ```
+#[derive(Clone)]
+pub struct FuzzyList<'a> {
+    block: Option<Block<'a>>,
+    items: Rc<Vec<FuzzyListItem<'a>>>,
+    /// Style used as a base style for the widget
+    style: Style,
+    start_corner: Corner,
+    /// Style used to render selected item, applied over the full width of its rows
+    highlight_style: Style,
+    /// Symbol in front of the selected item (Shift all items to the right)
+    highlight_symbol: Option<&'a str>,
+    /// Whether to repeat the highlight symbol for each line of the selected item
+    repeat_highlight_symbol: bool,
+    /// Whether to render 1-based line numbers in front of items
+    line_numbers: bool,
+    /// Style used to render line numbers
+    line_number_style: Style,
+    /// Whether line numbers refer to the original item index instead of the displayed position
+    original_line_numbers: bool,
+    /// Whether results appear one per tick after filtering
+    reveal_animation: bool,
+    /// Number of columns per indentation level
+    indent_width: u16,
+    /// Columns items built from cells are laid out into
+    columns: Vec<Column<'a>>,
+    /// Hint shown on the top row while nothing is selected
+    no_selection_hint: Option<Spans<'a>>,
+    /// Frames of an animated highlight symbol, takes precedence over `highlight_symbol`
+    highlight_symbol_frames: Option<&'a [&'a str]>,
+    /// Whether item timestamps are rendered as a relative time column
+    relative_time: bool,
+    /// Style used to render relative times
+    relative_time_style: Style,
+    /// Row fixed above the scrolling items
+    header_row: Option<Spans<'a>>,
+    /// Style used to render the header row
+    header_style: Style,
+    /// Style patched over the focused column of the selected row
+    focused_cell_style: Style,
+    /// Whether the offset may scroll past the point where the last item reaches the bottom
+    overscroll: bool,
+    /// Whether leading and trailing whitespace is rendered with a visible glyph
+    show_whitespace: bool,
+    /// Whether whitespace between words is rendered visibly as well
+    internal_whitespace: bool,
+    /// Style used to render visible whitespace
+    whitespace_style: Style,
+    /// Whether the selected item also renders its expanded content
+    expand_selected: bool,
+    /// Row pinned below the items
+    summary_row: Option<Spans<'a>>,
+    /// Marker drawn in a gutter next to the last confirmed item
+    last_confirmed_symbol: Option<&'a str>,
+    /// Whether items are flowed and wrapped like a paragraph instead of stacked in rows
+    flow: bool,
+    /// Text placed between items in flow mode
+    flow_separator: &'a str,
+    /// Height forcing every item to the same number of rows
+    item_height: Option<u16>,
+    /// Whether the list has focus, unfocused lists collapse to their summary
+    focused: bool,
+    /// Line shown in place of the items while unfocused
+    collapsed_summary: Option<Spans<'a>>,
+    /// Style patched over the context lines of items
+    context_style: Style,
+    /// Number of lines unselected items are collapsed to
+    collapsed_lines: Option<usize>,
+    /// Blank columns kept left and right of item content
+    item_padding: (u16, u16),
+    /// Whether highlighted runs of the selected item are labelled for jumping
+    number_matches: bool,
+    /// Style of the labels drawn over highlighted runs
+    match_number_style: Style,
+    /// Whether the selected item is drawn without its match highlights
+    hide_match_highlight_when_selected: bool,
+    /// Whether a match density column is drawn along the right edge
+    minimap: bool,
+    /// Style of the minimap column
+    minimap_style: Style,
+}
+
+impl<'a> FuzzyList<'a> {
+    pub fn new(items: Rc<Vec<FuzzyListItem<'a>>>) -> FuzzyList<'a> {
+        FuzzyList {
+            block: None,
+            style: Style::default(),
+            items,
+            start_corner: Corner::TopLeft,
+            highlight_style: Style::default(),
+            highlight_symbol: None,
+            repeat_highlight_symbol: false,
+            line_numbers: false,
+            line_number_style: Style::default(),
+            original_line_numbers: false,
+            reveal_animation: false,
+            indent_width: 2,
+            columns: vec![],
+            no_selection_hint: None,
+            highlight_symbol_frames: None,
+            relative_time: false,
+            relative_time_style: Style::default(),
+            header_row: None,
+            header_style: Style::default(),
+            focused_cell_style: Style::default(),
+            overscroll: false,
+            show_whitespace: false,
+            internal_whitespace: false,
+            whitespace_style: Style::default(),
+            expand_selected: false,
+            summary_row: None,
+            last_confirmed_symbol: None,
+            flow: false,
+            flow_separator: " ",
+            item_height: None,
+            focused: true,
+            collapsed_summary: None,
+            context_style: Style::default().add_modifier(Modifier::DIM),
+            collapsed_lines: None,
+            item_padding: (0, 0),
+            number_matches: false,
+            match_number_style: Style::default().add_modifier(Modifier::REVERSED),
+            hide_match_highlight_when_selected: false,
+            minimap: false,
+            minimap_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> FuzzyList<'a> {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> FuzzyList<'a> {
+        self.style = style;
+        self
+    }
+
+    pub fn highlight_symbol(mut self, highlight_symbol: &'a str) -> FuzzyList<'a> {
+        self.highlight_symbol = Some(highlight_symbol);
+        self
+    }
+
+    /// Animates the highlight symbol, showing the next frame on every [`FuzzyListState::tick`]
+    pub fn highlight_symbol_frames(mut self, frames: &'a [&'a str]) -> FuzzyList<'a> {
+        self.highlight_symbol_frames = Some(frames);
+        self
+    }
+
+    /// Style patched over every cell of the selected item's rows after its content is drawn,
+    /// so the background fills the row regardless of content length or alignment
+    pub fn highlight_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.highlight_style = style;
+        self
+    }
+
+    pub fn repeat_highlight_symbol(mut self, repeat: bool) -> FuzzyList<'a> {
+        self.repeat_highlight_symbol = repeat;
+        self
+    }
+
+    pub fn start_corner(mut self, corner: Corner) -> FuzzyList<'a> {
+        self.start_corner = corner;
+        self
+    }
+
+    pub fn line_numbers(mut self, line_numbers: bool) -> FuzzyList<'a> {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    pub fn line_number_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.line_number_style = style;
+        self
+    }
+
+    pub fn original_line_numbers(mut self, original: bool) -> FuzzyList<'a> {
+        self.original_line_numbers = original;
+        self
+    }
+
+    /// Reveals the results of each filter one per [`FuzzyListState::tick`] instead of all at once,
+    /// the unfiltered list is shown in full
+    pub fn reveal_animation(mut self, reveal_animation: bool) -> FuzzyList<'a> {
+        self.reveal_animation = reveal_animation;
+        self
+    }
+
+    pub fn indent_width(mut self, indent_width: u16) -> FuzzyList<'a> {
+        self.indent_width = indent_width;
+        self
+    }
+
+    /// Lays out items built with [`FuzzyListItem::cells`] into columns with a sticky header row
+    pub fn columns(mut self, columns: Vec<Column<'a>>) -> FuzzyList<'a> {
+        self.columns = columns;
+        self
+    }
+
+    /// Renders item timestamps as a right aligned relative time column, e.g. "2m ago"
+    pub fn relative_time(mut self, relative_time: bool) -> FuzzyList<'a> {
+        self.relative_time = relative_time;
+        self
+    }
+
+    pub fn relative_time_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.relative_time_style = style;
+        self
+    }
+
+    /// Row rendered above the items that never scrolls, is never selected nor matched
+    pub fn header_row(mut self, header_row: Option<Spans<'a>>) -> FuzzyList<'a> {
+        self.header_row = header_row;
+        self
+    }
+
+    pub fn header_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.header_style = style;
+        self
+    }
+
+    /// Marker, e.g. "•", drawn before the item set with [`FuzzyListState::set_last_confirmed`].
+    /// A gutter as wide as the marker is reserved on every row
+    pub fn last_confirmed_symbol(mut self, symbol: &'a str) -> FuzzyList<'a> {
+        self.last_confirmed_symbol = Some(symbol);
+        self
+    }
+
+    /// Flows the items one after another like a paragraph, wrapped within the list area, e.g. for a
+    /// compact display of matching tags. Nothing is selectable in this mode
+    pub fn flow(mut self, flow: bool) -> FuzzyList<'a> {
+        self.flow = flow;
+        self
+    }
+
+    /// Text placed between items in flow mode, a space by default
+    pub fn flow_separator(mut self, separator: &'a str) -> FuzzyList<'a> {
+        self.flow_separator = separator;
+        self
+    }
+
+    /// Renders every item `height` rows tall, padding shorter content and clipping taller content,
+    /// for a uniform grid-like list
+    pub fn item_height(mut self, height: Option<u16>) -> FuzzyList<'a> {
+        self.item_height = height;
+        self
+    }
+
+    /// Unfocused lists render only their [`FuzzyList::collapsed_summary`], e.g. while another pane
+    /// of a master-detail layout is active
+    pub fn focused(mut self, focused: bool) -> FuzzyList<'a> {
+        self.focused = focused;
+        self
+    }
+
+    /// Single line, e.g. "37 cities, 'ber' → 4 matches", rendered in place of the items while the
+    /// list is not focused
+    pub fn collapsed_summary(mut self, summary: Spans<'a>) -> FuzzyList<'a> {
+        self.collapsed_summary = Some(summary);
+        self
+    }
+
+    /// Style patched over the lines marked with [`FuzzyListItem::context_lines`]
+    pub fn context_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.context_style = style;
+        self
+    }
+
+    /// Shows at most `lines` lines of unselected items, ending with an ellipsis when lines are
+    /// hidden, while the selected item is rendered in full
+    pub fn collapsed_lines(mut self, lines: Option<usize>) -> FuzzyList<'a> {
+        self.collapsed_lines = lines;
+        self
+    }
+
+    /// Insets the content of each item by `left` and `right` columns within its row, the
+    /// highlight style still fills the whole row
+    pub fn item_padding(mut self, left: u16, right: u16) -> FuzzyList<'a> {
+        self.item_padding = (left, right);
+        self
+    }
+
+    /// Labels each highlighted run of the selected item, 1 to 9 then a to z, so that the host can
+    /// offer jumping to a match within a long item
+    pub fn number_matches(mut self, number_matches: bool) -> FuzzyList<'a> {
+        self.number_matches = number_matches;
+        self
+    }
+
+    /// Draws a match density column along the right edge of the items, each cell shaded by the
+    /// best match among the items it represents. Clicks are mapped back with
+    /// [`FuzzyListState::click_minimap`]
+    pub fn minimap(mut self, minimap: bool) -> FuzzyList<'a> {
+        self.minimap = minimap;
+        self
+    }
+
+    pub fn minimap_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.minimap_style = style;
+        self
+    }
+
+    pub fn match_number_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.match_number_style = style;
+        self
+    }
 
-impl<'a> FuzzyList<'a> {
-    pub fn new(items: Rc<Vec<FuzzyListItem<'a>>>) -> FuzzyList<'a> {
-        FuzzyList {
-            block: None,
-            style: Style::default(),
-            items,
-            start_corner: Corner::TopLeft,
-            highlight_style: Style::default(),
-            highlight_symbol: None,
-            repeat_highlight_symbol: false,
-        }
+    /// Draws the selected item from its content as given, without the filter highlights that may
+    /// clash with the highlight style
+    pub fn hide_match_highlight_when_selected(mut self, hide: bool) -> FuzzyList<'a> {
+        self.hide_match_highlight_when_selected = hide;
+        self
     }
 
-    pub fn block(mut self, block: Block<'a>) -> FuzzyList<'a> {
-        self.block = Some(block);
+    /// Row pinned at the bottom, below the items, e.g. a total computed by the host over the
+    /// filtered items. It takes one row off the items and never scrolls
+    pub fn summary_row(mut self, summary_row: Option<Spans<'a>>) -> FuzzyList<'a> {
+        self.summary_row = summary_row;
         self
     }
 
-    pub fn style(mut self, style: Style) -> FuzzyList<'a> {
-        self.style = style;
+    /// Hint drawn right aligned over the free space of the top row while nothing is selected
+    pub fn no_selection_hint(mut self, hint: Option<Spans<'a>>) -> FuzzyList<'a> {
+        self.no_selection_hint = hint;
         self
     }
 
-    pub fn highlight_symbol(mut self, highlight_symbol: &'a str) -> FuzzyList<'a> {
-        self.highlight_symbol = Some(highlight_symbol);
+    /// Style of the cell in [`FuzzyListState::focused_column`] on the selected row
+    pub fn focused_cell_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.focused_cell_style = style;
         self
     }
 
-    pub fn highlight_style(mut self, style: Style) -> FuzzyList<'a> {
-        self.highlight_style = style;
+    /// Renders leading and trailing whitespace of item lines as `·`, matching still uses the real content
+    pub fn show_whitespace(mut self, show_whitespace: bool) -> FuzzyList<'a> {
+        self.show_whitespace = show_whitespace;
         self
     }
 
-    pub fn repeat_highlight_symbol(mut self, repeat: bool) -> FuzzyList<'a> {
-        self.repeat_highlight_symbol = repeat;
+    /// Also renders whitespace between words visibly when `show_whitespace` is set
+    pub fn internal_whitespace(mut self, internal_whitespace: bool) -> FuzzyList<'a> {
+        self.internal_whitespace = internal_whitespace;
         self
     }
 
-    pub fn start_corner(mut self, corner: Corner) -> FuzzyList<'a> {
-        self.start_corner = corner;
+    pub fn whitespace_style(mut self, style: Style) -> FuzzyList<'a> {
+        self.whitespace_style = style;
+        self
+    }
+
+    /// Lets the list scroll until the last item sits at the top of the viewport, leaving blank rows
+    /// below it. When off the offset is clamped so that the last item stays at the bottom
+    pub fn overscroll(mut self, overscroll: bool) -> FuzzyList<'a> {
+        self.overscroll = overscroll;
         self
     }
 
+    /// Renders the [`FuzzyListItem::expanded_content`] of the selected item below it, the other
+    /// items keep their compact form
+    pub fn expand_selected(mut self, expand_selected: bool) -> FuzzyList<'a> {
+        self.expand_selected = expand_selected;
+        self
+    }
+
+    /// Renders items one after another, wrapped within `area`, highlights included
+    fn render_flow(&self, area: Rect, buf: &mut Buffer, state: &FuzzyListState<'a>) {
+        let mut spans: Vec<Span> = vec![];
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(self.flow_separator));
+            }
+            let content = item.display_content(state.ticks);
+            for (j, line) in content.lines.iter().enumerate() {
+                if j > 0 {
+                    spans.push(Span::raw(" "));
+                }
+                spans.extend(line.0.iter().map(|span| Span {
+                    content: span.content.clone(),
+                    style: item.style.patch(span.style),
+                }));
+            }
+        }
+        Paragraph::new(Spans::from(spans))
+            .style(self.style)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+
+    /// Draws one cell per bucket of displayed items, shaded by the best score within the bucket
+    /// relative to the best score overall. Buckets without a match stay blank
+    fn render_minimap(&self, area: Rect, buf: &mut Buffer, state: &FuzzyListState<'a>) {
+        const SHADES: [&str; 4] = ["░", "▒", "▓", "█"];
+        let score = |i: usize| state.filtered_scores.get(i).copied().flatten();
+        let best = (0..self.items.len()).filter_map(score).max().unwrap_or(0);
+        for row in 0..area.height {
+            let Some(bucket) = minimap_bucket(self.items.len(), area.height, row) else {
+                break;
+            };
+            if let Some(max) = bucket.filter_map(score).max() {
+                let shade = if best > 0 {
+                    (max.max(0) * (SHADES.len() as i64 - 1) / best) as usize
+                } else {
+                    0
+                };
+                buf.set_string(area.x, area.y + row, SHADES[shade], self.minimap_style);
+            }
+        }
+    }
+
+    /// Splits `area` horizontally into one area per column, keeping a space between columns
+    fn column_areas(&self, area: Rect) -> Vec<Rect> {
+        let constraints: Vec<Constraint> = self.columns.iter().map(|c| c.width).collect();
+        let areas = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(area);
+        let last = areas.len().saturating_sub(1);
+        areas
+            .iter()
+            .enumerate()
+            .map(|(k, a)| Rect {
+                width: if k < last {
+                    a.width.saturating_sub(1)
+                } else {
+                    a.width
+                },
+                ..*a
+            })
+            .collect()
+    }
+
+    fn line_number(&self, state: &FuzzyListState<'a>, index: usize) -> usize {
+        if self.original_line_numbers {
+            state.original_index(index).unwrap_or(index) + 1
+        } else {
+            index + 1
+        }
+    }
+
+    /// Number of rows taken by the content of `item`, forced to `item_height` when set and
+    /// limited to `collapsed_lines` unless selected
+    fn content_height(&self, item: &FuzzyListItem<'a>, is_selected: bool) -> usize {
+        let height = self
+            .item_height
+            .map_or_else(|| item.height(), |height| height.max(1) as usize);
+        match self.collapsed_lines {
+            Some(lines) if !is_selected => height.min(lines.max(1)),
+            _ => height,
+        }
+    }
+
+    /// Number of rows taken by `item`, growing with its expanded content while selected
+    fn display_height(&self, item: &FuzzyListItem<'a>, is_selected: bool) -> usize {
+        match &item.expanded_content {
+            Some(expanded) if is_selected && self.expand_selected => {
+                self.content_height(item, is_selected) + expanded.height()
+            }
+            _ => self.content_height(item, is_selected),
+        }
+    }
+
     fn get_items_bounds(
         &self,
         selected: Option<usize>,
         offset: usize,
         max_height: usize,
+        follow_selection: bool,
     ) -> (usize, usize) {
-        let offset = offset.min(self.items.len().saturating_sub(1));
+        let height_of = |i: usize| self.display_height(&self.items[i], selected == Some(i));
+        let max_offset = if self.overscroll {
+            self.items.len().saturating_sub(1)
+        } else {
+            // first offset from which the remaining items still fill the viewport
+            let mut height = 0;
+            let mut first = self.items.len();
+            while first > 0 && height + height_of(first - 1) <= max_height {
+                first -= 1;
+                height += height_of(first);
+            }
+            first.min(self.items.len().saturating_sub(1))
+        };
+        let offset = offset.min(max_offset);
         let mut start = offset;
         let mut end = offset;
         let mut height = 0;
-        for item in self.items.iter().skip(offset) {
-            if height + item.height() > max_height {
+        for i in offset..self.items.len() {
+            if height + height_of(i) > max_height {
                 break;
             }
-            height += item.height();
+            height += height_of(i);
             end += 1;
         }
 
+        if !follow_selection {
+            return (start, end);
+        }
         let selected = selected.unwrap_or(0).min(self.items.len() - 1);
 
+        // the selected item always stays within bounds, even when it is taller than the viewport
         while selected >= end {
-            height = height.saturating_add(self.items[end].height());
+            height = height.saturating_add(height_of(end));
             end += 1;
-            while height > max_height {
-                height = height.saturating_sub(self.items[start].height());
+            while height > max_height && start < selected {
+                height = height.saturating_sub(height_of(start));
                 start += 1;
             }
         }
         while selected < start {
             start -= 1;
-            height = height.saturating_add(self.items[start].height());
-            while height > max_height {
+            height = height.saturating_add(height_of(start));
+            while height > max_height && end > selected + 1 {
                 end -= 1;
-                height = height.saturating_sub(self.items[end].height());
+                height = height.saturating_sub(height_of(end));
             }
         }
         (start, end)
@@ -315,6 +2753,11 @@ impl<'a> StatefulWidget for FuzzyList<'a> {
 
     fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         buf.set_style(area, self.style);
+        state.rendered_rows.clear();
+        state.minimap_area = None;
+        if state.collect_stats {
+            state.stats.rows_rendered = 0;
+        }
         let list_area = match self.block.take() {
             Some(b) => {
                 let inner_area = b.inner(area);
@@ -328,35 +2771,157 @@ impl<'a> StatefulWidget for FuzzyList<'a> {
             return;
         }
 
+        if !self.focused {
+            // selection and offset are left untouched for when the list is focused again
+            if let Some(summary) = &self.collapsed_summary {
+                buf.set_spans(list_area.x, list_area.y, summary, list_area.width);
+            }
+            return;
+        }
+
+        // fixed header row and sticky column header take the first rows
+        let (header_row_area, list_area) = split_top_row(list_area, self.header_row.is_some());
+        let (header_area, list_area) = split_top_row(list_area, !self.columns.is_empty());
+        let (list_area, summary_area) = split_bottom_row(list_area, self.summary_row.is_some());
+        if let (Some(summary_row), Some(area)) = (&self.summary_row, summary_area) {
+            buf.set_spans(area.x, area.y, summary_row, area.width);
+        }
+        if let (Some(header_row), Some(area)) = (&self.header_row, header_row_area) {
+            buf.set_style(area, self.header_style);
+            buf.set_spans(area.x, area.y, header_row, area.width);
+        }
+
         if self.items.is_empty() {
             return;
         }
 
+        if self.flow {
+            self.render_flow(list_area, buf, state);
+            return;
+        }
+
+        // the minimap takes the rightmost column of the item rows
+        let list_area = if self.minimap && list_area.width > 1 {
+            let minimap_area = Rect {
+                x: list_area.right() - 1,
+                width: 1,
+                ..list_area
+            };
+            self.render_minimap(minimap_area, buf, state);
+            state.minimap_area = Some(minimap_area);
+            Rect {
+                width: list_area.width - 1,
+                ..list_area
+            }
+        } else {
+            list_area
+        };
+
         let list_height = list_area.height as usize;
 
-        let (start, end) = self.get_items_bounds(state.selected, state.offset, list_height);
+        let (start, end) =
+            self.get_items_bounds(state.selected, state.offset, list_height, !state.detached);
+        state.viewport_height = list_height;
         state.offset = start;
 
-        let highlight_symbol = self.highlight_symbol.unwrap_or("");
+        let highlight_symbol = match self.highlight_symbol_frames {
+            Some(frames) if !frames.is_empty() => {
+                // pad frames to the widest one so content does not shift while animating
+                let width = frames.iter().map(|f| f.width()).max().unwrap_or(0);
+                let frame = frames[state.ticks % frames.len()];
+                format!("{}{}", frame, " ".repeat(width - frame.width()))
+            }
+            _ => self.highlight_symbol.unwrap_or("").to_string(),
+        };
+        let highlight_symbol = highlight_symbol.as_str();
         let blank_symbol = " ".repeat(highlight_symbol.width());
 
+        // gutter wide enough for the largest visible line number plus a separating space
+        let line_number_width = if self.line_numbers {
+            (start..end)
+                .map(|i| self.line_number(state, i).to_string().len())
+                .max()
+                .unwrap_or(0)
+                + 1
+        } else {
+            0
+        };
+
+        // column wide enough for the longest visible relative time plus a separating space
+        let now = SystemTime::now();
+        let time_width = if self.relative_time {
+            self.items[start..end]
+                .iter()
+                .filter_map(|item| item.timestamp)
+                .map(|timestamp| format_relative_time(timestamp, now).width() as u16 + 1)
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        let last_confirmed_width = self.last_confirmed_symbol.map_or(0, |s| s.width() as u16);
+
+        let blank_line = Spans::default();
+        let mut rendered_rows = vec![];
         let mut current_height = 0;
-        let has_selection = state.selected.is_some();
+        let has_selection = state.has_selection();
+        // end of the content drawn on the top row, hints may only use the space after it
+        let mut top_row_end = list_area.left();
+
+        if let Some(header_area) = header_area {
+            let gutter = line_number_width as u16
+                + last_confirmed_width
+                + self.item_padding.0
+                + if has_selection {
+                    highlight_symbol.width() as u16
+                } else {
+                    0
+                };
+            let gutter = gutter.min(header_area.width);
+            let cells_area = Rect {
+                x: header_area.x + gutter,
+                width: header_area.width - gutter,
+                ..header_area
+            };
+            for (column, area) in self
+                .columns
+                .iter()
+                .zip(self.column_areas(cells_area).iter())
+            {
+                set_aligned_spans(buf, *area, &column.header, column.alignment);
+            }
+        }
         for (i, item) in self
             .items
             .iter()
             .enumerate()
             .skip(state.offset)
             .take(end - start)
+            // results of a filter appear one by one from the top of the viewport
+            .take_while(|(i, _)| {
+                !self.reveal_animation
+                    || state.filter.is_none()
+                    || i - state.offset < state.revealed()
+            })
         {
+            let is_selected = state.selected.map(|s| s == i).unwrap_or(false);
+            let is_last_confirmed =
+                state.last_confirmed.is_some() && state.original_index(i) == state.last_confirmed;
+            // an item taller than the remaining rows is cut off at the viewport edge
+            let height = (self.display_height(item, is_selected) as u16)
+                .min(list_area.height - current_height);
+            if height == 0 {
+                break;
+            }
             let (x, y) = match self.start_corner {
                 Corner::BottomLeft => {
-                    current_height += item.height() as u16;
+                    current_height += height;
                     (list_area.left(), list_area.bottom() - current_height)
                 }
                 _ => {
                     let pos = (list_area.left(), list_area.top() + current_height);
-                    current_height += item.height() as u16;
+                    current_height += height;
                     pos
                 }
             };
@@ -364,13 +2929,89 @@ impl<'a> StatefulWidget for FuzzyList<'a> {
                 x,
                 y,
                 width: list_area.width,
-                height: item.height() as u16,
+                height,
             };
+            rendered_rows.push((y..y + height, i));
             let item_style = self.style.patch(item.style);
             buf.set_style(area, item_style);
 
-            let is_selected = state.selected.map(|s| s == i).unwrap_or(false);
-            for (j, line) in item.content.lines.iter().enumerate() {
+            let depth = item
+                .indent
+                .or_else(|| state.original_index(i).map(|o| state.depth(o)))
+                .unwrap_or(0);
+            let indent = (depth as u16).saturating_mul(self.indent_width);
+            let time = item
+                .timestamp
+                .filter(|_| self.relative_time)
+                .map(|timestamp| format_relative_time(timestamp, now));
+            // the selection alone marks the selected item, its matches are left uncolored
+            let pristine = is_selected
+                && self.hide_match_highlight_when_selected
+                && item.generator.is_none()
+                && !item.loading;
+            let content = if pristine {
+                Cow::Borrowed(item.content())
+            } else {
+                item.display_content(state.ticks)
+            };
+            let expanded = item
+                .expanded_content
+                .as_ref()
+                .filter(|_| is_selected && self.expand_selected)
+                .map(|expanded| expanded.lines.as_slice())
+                .unwrap_or_default();
+            let content_height = self.content_height(item, is_selected);
+            // collapsed items end with an ellipsis hinting at the hidden lines
+            let collapsed = content_height < self.content_height(item, true);
+            // short content is padded with blank lines so that expanded content starts below it
+            let lines = content
+                .lines
+                .iter()
+                .take(item.height())
+                .chain(iter::repeat(&blank_line))
+                .take(content_height)
+                .chain(expanded)
+                .take(height as usize);
+            let mut cells_area = None;
+            // highlighted runs are labelled in order across the lines of the item
+            let number_matches = is_selected
+                && !pristine
+                && self.number_matches
+                && item.highlighted.is_some()
+                && item.generator.is_none()
+                && !item.loading;
+            for (j, line) in lines.enumerate() {
+                let x = if self.line_numbers {
+                    if j == 0 {
+                        let number = format!(
+                            "{:>width$} ",
+                            self.line_number(state, i),
+                            width = line_number_width - 1
+                        );
+                        buf.set_stringn(
+                            x,
+                            y + j as u16,
+                            number,
+                            list_area.width as usize,
+                            item_style.patch(self.line_number_style),
+                        );
+                    }
+                    x + (line_number_width as u16).min(list_area.width)
+                } else {
+                    x
+                };
+                // the gutter is kept on every row so that content does not shift
+                let x = match self.last_confirmed_symbol {
+                    Some(marker) => {
+                        let width = last_confirmed_width.min(list_area.right() - x);
+                        if j == 0 && is_last_confirmed {
+                            buf.set_stringn(x, y, marker, width as usize, item_style);
+                        }
+                        x + width
+                    }
+                    None => x,
+                };
+                let row_width = list_area.width - (x - list_area.left());
                 // if the item is selected, we need to display the hightlight symbol:
                 // - either for the first line of the item only,
                 // - or for each line of the item if the appropriate option is set
@@ -379,23 +3020,143 @@ impl<'a> StatefulWidget for FuzzyList<'a> {
                 } else {
                     &blank_symbol
                 };
-                let (elem_x, max_element_width) = if has_selection {
-                    let (elem_x, _) = buf.set_stringn(
-                        x,
-                        y + j as u16,
-                        symbol,
-                        list_area.width as usize,
-                        item_style,
-                    );
-                    (elem_x, (list_area.width - (elem_x - x)))
+                // the symbol is truncated to the row, which may leave no room for content
+                let (elem_x, max_element_width) = if has_selection && row_width > 0 {
+                    let (elem_x, _) =
+                        buf.set_stringn(x, y + j as u16, symbol, row_width as usize, item_style);
+                    (elem_x, (row_width - (elem_x - x)))
+                } else {
+                    (x, row_width)
+                };
+                // reserve the timestamp column at the right edge
+                let max_element_width = max_element_width.saturating_sub(time_width);
+                if let (0, Some(time)) = (j, &time) {
+                    let time_x = list_area.right().saturating_sub(time.width() as u16);
+                    if time_x >= elem_x + max_element_width {
+                        buf.set_stringn(
+                            time_x,
+                            y,
+                            time,
+                            time.width(),
+                            item_style.patch(self.relative_time_style),
+                        );
+                    }
+                }
+                // padding insets the content while the row highlight still covers it
+                let padding_left = self.item_padding.0.min(max_element_width);
+                let elem_x = elem_x + padding_left;
+                let max_element_width =
+                    (max_element_width - padding_left).saturating_sub(self.item_padding.1);
+                if max_element_width == 0 {
+                    continue;
+                }
+                let indent = indent.min(max_element_width);
+                if j == 0 {
+                    cells_area = Some(Rect {
+                        x: elem_x + indent,
+                        width: max_element_width - indent,
+                        ..area
+                    });
+                }
+                if y + j as u16 == list_area.top() {
+                    top_row_end = if item.cells.is_some() {
+                        list_area.right()
+                    } else {
+                        elem_x + indent + line.width() as u16
+                    };
+                }
+                let labels: Vec<(u16, char)> = if number_matches {
+                    item.match_runs
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &(line, _))| line == j)
+                        .filter_map(|(n, &(_, offset))| Some((offset, match_label(n)?)))
+                        .collect()
+                } else {
+                    vec![]
+                };
+                let line = if self.show_whitespace {
+                    Cow::Owned(visible_whitespace(
+                        line,
+                        self.internal_whitespace,
+                        self.whitespace_style,
+                    ))
                 } else {
-                    (x, list_area.width)
+                    Cow::Borrowed(line)
                 };
-                buf.set_spans(elem_x, y + j as u16, line, max_element_width);
+                let line = if collapsed && j + 1 == content_height {
+                    let mut line = line.into_owned();
+                    line.0.push(Span::raw("…"));
+                    Cow::Owned(line)
+                } else {
+                    line
+                };
+                buf.set_spans(
+                    elem_x + indent,
+                    y + j as u16,
+                    &line,
+                    max_element_width - indent,
+                );
+                for (offset, label) in labels {
+                    if offset < max_element_width - indent {
+                        buf.set_string(
+                            elem_x + indent + offset,
+                            y + j as u16,
+                            label.to_string(),
+                            self.match_number_style,
+                        );
+                    }
+                }
+                if j < item.height() && item.context_lines.contains(&j) {
+                    let line_area = Rect {
+                        x: elem_x + indent,
+                        y: y + j as u16,
+                        width: max_element_width - indent,
+                        height: 1,
+                    };
+                    buf.set_style(line_area, self.context_style);
+                }
+            }
+            let cells = item.highlighted_cells.as_ref().or(item.cells.as_ref());
+            let mut focused_area = None;
+            if let (Some(cells), Some(cells_area), false) = (cells, cells_area, item.loading) {
+                let column_areas = self.column_areas(cells_area);
+                for (k, (cell, column)) in cells.iter().zip(self.columns.iter()).enumerate() {
+                    let column_area = column_areas[k];
+                    if is_selected && k == state.focused_column {
+                        focused_area = Some(column_area);
+                    }
+                    let cell_height = content_height.min(area.height as usize);
+                    for (j, line) in cell.lines.iter().take(cell_height).enumerate() {
+                        let line_area = Rect {
+                            y: column_area.y + j as u16,
+                            height: 1,
+                            ..column_area
+                        };
+                        set_aligned_spans(buf, line_area, line, column.alignment);
+                    }
+                }
             }
             if is_selected {
+                // area spans the whole list width, covering gutters and trailing blanks
                 buf.set_style(area, self.highlight_style);
             }
+            if let Some(focused_area) = focused_area {
+                buf.set_style(focused_area, self.focused_cell_style);
+            }
+        }
+        state.rendered_rows = rendered_rows;
+        if state.collect_stats {
+            state.stats.rows_rendered = current_height as usize;
+        }
+
+        if let (Some(hint), false) = (&self.no_selection_hint, has_selection) {
+            let width = hint.width() as u16;
+            // keep a blank column between content and hint
+            if list_area.right().saturating_sub(top_row_end) > width {
+                let x = list_area.right() - width;
+                buf.set_spans(x, list_area.top(), hint, width);
+            }
         }
     }
 }
@@ -406,3 +3167,646 @@ impl<'a> Widget for FuzzyList<'a> {
         StatefulWidget::render(self, area, buf, &mut state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(items: &[&'static str]) -> FuzzyListState<'static> {
+        FuzzyListState::with_items(items.iter().map(|&i| FuzzyListItem::new(i)).collect())
+    }
+
+    fn shown(state: &FuzzyListState) -> Vec<String> {
+        state.get_items().iter().map(|i| i.search_text()).collect()
+    }
+
+    #[test]
+    fn update_item_refilters_new_content() {
+        let mut state = state(&["apple", "banana"]);
+        state.set_filter(Some("ban"));
+        assert_eq!(shown(&state), ["banana"]);
+
+        state.update_item(0, FuzzyListItem::new("banana split"));
+        assert_eq!(shown(&state), ["banana split", "banana"]);
+
+        state.update_item(1, FuzzyListItem::new("cherry"));
+        assert_eq!(shown(&state), ["banana split"]);
+    }
+
+    #[test]
+    fn navigation_stays_within_filtered_items() {
+        let mut state = state(&["ant", "bee", "anteater", "cat", "antelope"]);
+        state.set_filter(Some("ant"));
+        assert_eq!(state.effective_len(), 3);
+        for _ in 0..5 {
+            state.increment_selected();
+        }
+        assert_eq!(state.selected(), Some(2));
+        assert_eq!(state.increment_selected(), NavOutcome::EscapeBottom);
+        assert_eq!(state.selected(), Some(2));
+    }
+
+    #[test]
+    fn highlight_keeps_grapheme_clusters_whole() {
+        // the flag is two regional indicator characters forming one cluster
+        let content = "a🇹🇷b";
+        let style = Style::default().fg(Color::Red);
+        // only the first of the two characters matched
+        let first_half = 1..2;
+        let spans = highlight_ranges(content, &[first_half], style);
+        let contents: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(contents, ["a", "🇹🇷", "b"]);
+        assert_eq!(spans[1].style, style);
+
+        // a match starting on the second character
+        let second_half = 2..4;
+        let spans = highlight_ranges(content, &[second_half], style);
+        let contents: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(contents, ["a", "🇹🇷b"]);
+    }
+
+    #[test]
+    fn dedup_keeps_first_city() {
+        let mut state = FuzzyListState::with_items(vec![
+            FuzzyListItem::styled("Paris", Style::default().fg(Color::Blue)),
+            FuzzyListItem::new("London"),
+            FuzzyListItem::new("Paris"),
+        ]);
+        state.dedup_by_key(Rc::new(|item| item.search_text()));
+        assert_eq!(shown(&state), ["Paris", "London"]);
+        assert_eq!(state.get_items()[0].style, Style::default().fg(Color::Blue));
+
+        state.append_items(vec![
+            FuzzyListItem::new("London"),
+            FuzzyListItem::new("Rome"),
+        ]);
+        assert_eq!(shown(&state), ["Paris", "London", "Rome"]);
+    }
+
+    fn render<'a>(
+        list: FuzzyList<'a>,
+        state: &mut FuzzyListState<'a>,
+        width: u16,
+        height: u16,
+    ) -> Buffer {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(list, area, &mut buf, state);
+        buf
+    }
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf.get(x, y).symbol.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn wide_highlight_symbol_is_truncated() {
+        let mut state = state(&["apple", "banana"]);
+        state.select(Some(0));
+        let items = state.get_items();
+        let list = FuzzyList::new(items).highlight_symbol("0123456789");
+        let buf = render(list, &mut state, 4, 2);
+        assert_eq!(row(&buf, 0), "0123");
+        assert_eq!(row(&buf, 1), "    ");
+    }
+
+    #[test]
+    fn overscroll_lets_last_item_reach_top() {
+        let state = state(&["a", "b", "c", "d", "e"]);
+        let list = FuzzyList::new(state.get_items()).overscroll(true);
+        assert_eq!(list.get_items_bounds(None, 4, 3, false), (4, 5));
+        assert_eq!(list.get_items_bounds(None, 9, 3, false), (4, 5));
+        assert_eq!(list.get_items_bounds(None, 1, 3, false), (1, 4));
+
+        let mut state = state.clone();
+        state.scroll(4);
+        let buf = render(list, &mut state, 1, 3);
+        assert_eq!([row(&buf, 0), row(&buf, 1), row(&buf, 2)], ["e", " ", " "]);
+    }
+
+    #[test]
+    fn offset_is_clamped_without_overscroll() {
+        let state = state(&["a", "b", "c", "d", "e"]);
+        let list = FuzzyList::new(state.get_items());
+        // the last items still fill the viewport
+        assert_eq!(list.get_items_bounds(None, 4, 3, false), (2, 5));
+        assert_eq!(list.get_items_bounds(None, 1, 3, false), (1, 4));
+        // everything fits, nothing to scroll
+        assert_eq!(list.get_items_bounds(None, 3, 10, false), (0, 5));
+        // the clamp counts multi-line items by their height
+        let items = Rc::new(vec![
+            FuzzyListItem::new("a"),
+            FuzzyListItem::new("b"),
+            FuzzyListItem::new("c\nc"),
+        ]);
+        let list = FuzzyList::new(items);
+        assert_eq!(list.get_items_bounds(None, 2, 3, false), (1, 3));
+
+        let mut state = state.clone();
+        state.scroll(4);
+        let list = FuzzyList::new(state.get_items());
+        let buf = render(list, &mut state, 1, 3);
+        assert_eq!([row(&buf, 0), row(&buf, 1), row(&buf, 2)], ["c", "d", "e"]);
+    }
+
+    #[test]
+    fn score_order_keeps_ancestors_above_children() {
+        let mut state = FuzzyListState::with_items(vec![
+            FuzzyListItem::new("src"),
+            FuzzyListItem::new("lib.rs").parent(Some(0)),
+            FuzzyListItem::new("docs"),
+            FuzzyListItem::new("librarian.md").parent(Some(2)),
+        ]);
+        state.set_keep_ancestors(true);
+        state.set_filter(Some("lib"));
+        assert_eq!(shown(&state), ["src", "lib.rs", "docs", "librarian.md"]);
+
+        state.set_filter(None);
+        state.set_filter_live("li");
+        state.set_filter_live("lib");
+        assert_eq!(shown(&state), ["src", "lib.rs", "docs", "librarian.md"]);
+
+        // subtrees are ranked by their best match
+        state.set_items(vec![
+            FuzzyListItem::new("src"),
+            FuzzyListItem::new("l_i_b.rs").parent(Some(0)),
+            FuzzyListItem::new("docs"),
+            FuzzyListItem::new("lib.md").parent(Some(2)),
+        ]);
+        assert_eq!(shown(&state), ["docs", "lib.md", "src", "l_i_b.rs"]);
+    }
+
+    #[test]
+    fn passthrough_keeps_host_order() {
+        let mut state = state(&["zzz", "xab", "ab"]);
+        state.set_passthrough(true);
+        state.set_filter(Some("ab"));
+        assert_eq!(shown(&state), ["zzz", "xab", "ab"]);
+    }
+
+    #[test]
+    fn romanization_matches_are_ranked_by_their_score() {
+        let mut state = FuzzyListState::with_items(vec![
+            FuzzyListItem::new("tokoyo town"),
+            FuzzyListItem::new("東京").romanization("tokyo"),
+        ]);
+        state.set_filter(Some("tokyo"));
+        assert_eq!(shown(&state), ["東京", "tokoyo town"]);
+    }
+
+    #[test]
+    fn tiebreak_key_orders_equal_scores() {
+        let mut state = state(&["b-ab", "a-ab", "ab"]);
+        state.set_filter(Some("ab"));
+        assert_eq!(shown(&state), ["ab", "b-ab", "a-ab"]);
+
+        state.set_tiebreak_key(Some(Rc::new(|item| item.search_text())));
+        assert_eq!(shown(&state), ["ab", "a-ab", "b-ab"]);
+    }
+
+    #[test]
+    fn matched_iter_reports_filter_scores() {
+        let mut state = FuzzyListState::with_items(vec![
+            FuzzyListItem::new("tokoyo town"),
+            FuzzyListItem::new("Help").always_visible(true),
+            FuzzyListItem::new("東京").romanization("tokyo"),
+            FuzzyListItem::new("paris"),
+        ]);
+        assert!(state.matched_iter().all(|(_, score, _)| score.is_none()));
+
+        state.set_filter(Some("tokyo"));
+        let matched: Vec<(usize, bool)> = state
+            .matched_iter()
+            .map(|(i, score, _)| (i, score.is_some()))
+            .collect();
+        assert_eq!(matched, [(2, true), (0, true), (1, false)]);
+    }
+
+    #[test]
+    fn order_modes() {
+        let mut state = state(&["xaxb", "zab", "ab", "cde"]);
+        state.set_filter(Some("ab"));
+        assert_eq!(shown(&state), ["ab", "zab", "xaxb"]);
+
+        state.set_order_mode(OrderMode::Original);
+        assert_eq!(shown(&state), ["xaxb", "zab", "ab"]);
+
+        state.set_order_mode(OrderMode::Alphabetical);
+        assert_eq!(shown(&state), ["ab", "xaxb", "zab"]);
+
+        // the order mode applies to later filters as well
+        state.set_filter(Some("b"));
+        assert_eq!(shown(&state), ["ab", "xaxb", "zab"]);
+    }
+
+    #[test]
+    fn toggle_case_mode_refilters() {
+        let mut state = state(&["Apple", "apple", "APPLE"]);
+        state.set_filter(Some("apple"));
+        assert_eq!(state.case_mode(), CaseMode::Smart);
+        assert_eq!(state.effective_len(), 3);
+
+        assert_eq!(state.toggle_case_mode(), CaseMode::Ignore);
+        assert_eq!(state.effective_len(), 3);
+
+        assert_eq!(state.toggle_case_mode(), CaseMode::Respect);
+        assert_eq!(shown(&state), ["apple"]);
+
+        assert_eq!(state.toggle_case_mode(), CaseMode::Smart);
+        assert_eq!(state.effective_len(), 3);
+
+        state.set_filter(Some("Apple"));
+        assert_eq!(shown(&state), ["Apple"]);
+        assert_eq!(state.toggle_case_mode(), CaseMode::Ignore);
+        assert_eq!(state.effective_len(), 3);
+    }
+
+    #[test]
+    fn auto_select_top_selects_first_result() {
+        let mut state = state(&["cherry", "banana", "bandana"]);
+        state.set_auto_select_top(true);
+        state.set_filter(Some("ban"));
+        assert_eq!(state.selected(), Some(0));
+
+        state.increment_selected();
+        state.set_filter(Some("band"));
+        assert_eq!(state.selected(), Some(0));
+        assert_eq!(shown(&state), ["bandana"]);
+
+        state.set_auto_select_top(false);
+        state.set_filter(Some("b"));
+        assert_eq!(state.selected(), None);
+    }
+
+    #[test]
+    fn append_items_follows_the_tail() {
+        let mut state = state(&["a", "b"]);
+        state.set_follow(true);
+        assert_eq!(state.selected(), Some(1));
+        state.append_items(vec![FuzzyListItem::new("c"), FuzzyListItem::new("d")]);
+        assert_eq!(state.selected(), Some(3));
+        assert!(state.is_following());
+
+        // navigating stops following
+        state.decrement_selected();
+        assert!(!state.is_following());
+        state.append_items(vec![FuzzyListItem::new("e")]);
+        assert_eq!(state.selected(), Some(2));
+        assert_eq!(state.effective_len(), 5);
+    }
+
+    #[test]
+    fn item_padding_insets_content() {
+        let mut state = state(&["abcdefghij", "xy"]);
+        state.select(Some(0));
+        let highlight = Style::default().bg(Color::Blue);
+        let list = FuzzyList::new(state.get_items())
+            .item_padding(2, 2)
+            .highlight_style(highlight);
+        let buf = render(list, &mut state, 10, 2);
+        assert_eq!(row(&buf, 0), "  abcdef  ");
+        assert_eq!(row(&buf, 1), "  xy      ");
+        // padding is part of the highlighted row
+        assert!((0..10).all(|x| buf.get(x, 0).bg == Color::Blue));
+
+        let list = FuzzyList::new(state.get_items())
+            .item_padding(1, 3)
+            .highlight_symbol(">");
+        let buf = render(list, &mut state, 10, 2);
+        assert_eq!(row(&buf, 0), "> abcde   ");
+        assert_eq!(row(&buf, 1), "  xy      ");
+    }
+
+    #[test]
+    fn selected_clipped_detects_tall_items() {
+        let mut state = state(&["one", "a\nb\nc\nd", "two"]);
+        assert!(!state.selected_clipped(3));
+        state.select(Some(0));
+        assert!(!state.selected_clipped(3));
+        state.select(Some(1));
+        assert!(state.selected_clipped(3));
+        assert!(!state.selected_clipped(4));
+
+        // the tall item is still drawn, clipped to the viewport
+        let list = FuzzyList::new(state.get_items());
+        let buf = render(list, &mut state, 1, 3);
+        assert_eq!([row(&buf, 0), row(&buf, 1), row(&buf, 2)], ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn selected_row_can_hide_match_highlight() {
+        let mut state = state(&["new york", "newark"]);
+        state.set_filter(Some("new"));
+        state.select(Some(0));
+        let red = FuzzyListItem::default_filter_style().fg;
+        let fg = |buf: &Buffer, x: u16, y: u16| buf.get(x, y).style().fg;
+
+        let list = FuzzyList::new(state.get_items());
+        let buf = render(list, &mut state, 8, 2);
+        assert_eq!(fg(&buf, 0, 0), red);
+        assert_eq!(fg(&buf, 0, 1), red);
+
+        let list = FuzzyList::new(state.get_items()).hide_match_highlight_when_selected(true);
+        let buf = render(list, &mut state, 8, 2);
+        assert_eq!(row(&buf, 0), "new york");
+        assert!((0..8).all(|x| fg(&buf, x, 0) == Some(Color::Reset)));
+        // other rows keep their highlights
+        assert_eq!(fg(&buf, 0, 1), red);
+    }
+
+    fn spans(text: &Text) -> Vec<(String, bool)> {
+        let filter_style = FuzzyListItem::default_filter_style();
+        text.lines
+            .iter()
+            .flat_map(|line| line.0.iter())
+            .map(|span| (span.content.to_string(), span.style == filter_style))
+            .collect()
+    }
+
+    #[test]
+    fn accented_cities_highlight_whole_characters() {
+        let mut state = state(&["Kraków", "São Paulo", "Zürich"]);
+        state.set_filter(Some("aków"));
+        assert_eq!(shown(&state), ["Kraków"]);
+        let text = state.rendered_item_text(0).unwrap();
+        assert_eq!(
+            spans(&text),
+            [("Kr".to_string(), false), ("aków".to_string(), true)]
+        );
+
+        state.set_filter(Some("ãop"));
+        let text = state.rendered_item_text(0).unwrap();
+        assert_eq!(
+            spans(&text),
+            [
+                ("S".to_string(), false),
+                ("ão".to_string(), true),
+                (" ".to_string(), false),
+                ("P".to_string(), true),
+                ("aulo".to_string(), false),
+            ]
+        );
+
+        state.set_filter(Some("üri"));
+        let text = state.rendered_item_text(0).unwrap();
+        assert_eq!(
+            spans(&text),
+            [
+                ("Z".to_string(), false),
+                ("üri".to_string(), true),
+                ("ch".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_fills_the_selected_row() {
+        let highlight = Style::default().bg(Color::Blue);
+        let row_highlighted =
+            |buf: &Buffer, y: u16| (0..buf.area.width).all(|x| buf.get(x, y).bg == Color::Blue);
+
+        let mut state = state(&["ab", "a much longer item"]);
+        state.select(Some(0));
+        let list = FuzzyList::new(state.get_items())
+            .highlight_style(highlight)
+            .highlight_symbol("> ");
+        let buf = render(list, &mut state, 10, 2);
+        assert_eq!(row(&buf, 0), "> ab      ");
+        assert!(row_highlighted(&buf, 0));
+        assert!(!row_highlighted(&buf, 1));
+
+        // padded content still highlights the padding and the trailing blanks
+        let list = FuzzyList::new(state.get_items())
+            .highlight_style(highlight)
+            .item_padding(1, 2);
+        let buf = render(list, &mut state, 10, 2);
+        assert_eq!(row(&buf, 0), " ab       ");
+        assert!(row_highlighted(&buf, 0));
+
+        // aligned cells leave blanks on both sides of short content
+        let mut state = FuzzyListState::with_items(vec![
+            FuzzyListItem::cells(vec![Text::raw("a"), Text::raw("b")]),
+            FuzzyListItem::cells(vec![Text::raw("c"), Text::raw("d")]),
+        ]);
+        state.select(Some(0));
+        let columns = vec![
+            Column::new("", Constraint::Length(5)).alignment(Alignment::Right),
+            Column::new("", Constraint::Length(5)).alignment(Alignment::Center),
+        ];
+        let list = FuzzyList::new(state.get_items())
+            .highlight_style(highlight)
+            .columns(columns);
+        let buf = render(list, &mut state, 12, 3);
+        // the first row holds the column headers
+        assert_eq!(row(&buf, 1), "   a    b   ");
+        assert!(row_highlighted(&buf, 1));
+        assert!(!row_highlighted(&buf, 2));
+    }
+
+    #[test]
+    fn reveal_animation_staggers_filtered_results() {
+        let mut state = state(&["a", "ab", "abc", "abcd", "x"]);
+        let rows = |state: &mut FuzzyListState<'static>| {
+            let list = FuzzyList::new(state.get_items()).reveal_animation(true);
+            let buf = render(list, state, 4, 3);
+            [row(&buf, 0), row(&buf, 1), row(&buf, 2)]
+        };
+        // nothing to stagger without a filter
+        assert_eq!(rows(&mut state), ["a   ", "ab  ", "abc "]);
+
+        state.set_filter(Some("a"));
+        assert_eq!(rows(&mut state)[1..], ["    ", "    "]);
+        state.tick();
+        assert_eq!(rows(&mut state)[2], "    ");
+        state.tick();
+        assert!(rows(&mut state).iter().all(|row| row.trim() != ""));
+
+        // reveals count from the top of the viewport
+        state.set_items(
+            ["a1", "a2", "a3", "a4", "a5", "a6"]
+                .map(FuzzyListItem::new)
+                .to_vec(),
+        );
+        state.set_filter(Some("a"));
+        state.select(Some(5));
+        assert_eq!(rows(&mut state), ["a4  ", "    ", "    "]);
+    }
+
+    #[test]
+    fn number_matches_labels_recorded_runs() {
+        let mut state = state(&["New York", "Newark"]);
+        state.set_filter(Some("nwyk"));
+        state.select(Some(0));
+        let list = FuzzyList::new(state.get_items()).number_matches(true);
+        let buf = render(list, &mut state, 9, 1);
+        assert_eq!(row(&buf, 0), "1e2 3or4 ");
+
+        // styled content that did not match is not labelled
+        let content = Spans::from(vec![
+            Span::styled("xx", Style::default().fg(Color::Green)),
+            Span::raw(" new york"),
+        ]);
+        let item = FuzzyListItem::new(content)
+            .search_key("new york")
+            .search_key_map(Rc::new(|i| Some(i + 3)));
+        let mut state = FuzzyListState::with_items(vec![item]);
+        state.set_filter(Some("ny"));
+        state.select(Some(0));
+        let list = FuzzyList::new(state.get_items()).number_matches(true);
+        let buf = render(list, &mut state, 11, 1);
+        assert_eq!(row(&buf, 0), "xx 1ew 2ork");
+    }
+
+    #[test]
+    fn filtered_to_string_reflects_the_filter() {
+        let mut state = state(&["a", "b", "ab"]);
+        assert_eq!(state.filtered_to_string(), "a\nb\nab");
+        state.set_filter(Some("b"));
+        assert_eq!(state.filtered_to_string(), "b\nab");
+        state.set_filter(Some("zzz"));
+        assert_eq!(state.filtered_to_string(), "");
+    }
+
+    #[test]
+    fn unique_match_needs_a_single_shown_item() {
+        let mut state = state(&["abc", "xyz", "qqq"]);
+        state.set_filter(Some("abc"));
+        assert!(state.is_unique_match());
+
+        state.set_passthrough(true);
+        assert_eq!(state.effective_len(), 3);
+        assert!(!state.is_unique_match());
+
+        state.set_passthrough(false);
+        state.set_filter(None);
+        assert!(!state.is_unique_match());
+    }
+
+    #[test]
+    fn auto_accept_only_confirms_new_queries() {
+        let confirmed = Rc::new(Cell::new(0));
+        let mut state = state(&["apple", "banana", "cherry"]);
+        state.set_auto_accept_single(true);
+        let counter = confirmed.clone();
+        state.set_on_confirm(Some(Rc::new(move |_| counter.set(counter.get() + 1))));
+
+        state.set_filter(Some("ban"));
+        assert_eq!(confirmed.get(), 1);
+        assert_eq!(state.selected(), Some(0));
+
+        // refreshing items or matching options refilters without confirming again
+        state.append_items(vec![FuzzyListItem::new("date")]);
+        state.update_item(0, FuzzyListItem::new("apricot"));
+        state.set_case_mode(CaseMode::Ignore);
+        state.set_filter(Some("ban"));
+        assert_eq!(confirmed.get(), 1);
+
+        state.set_filter_live("bana");
+        assert_eq!(confirmed.get(), 2);
+    }
+
+    #[test]
+    fn events_are_only_recorded_when_enabled() {
+        let mut state = state(&["apple", "banana"]);
+        state.set_filter(Some("a"));
+        state.increment_selected();
+        assert!(state.take_events().is_empty());
+
+        state.set_record_events(true);
+        state.increment_selected();
+        state.set_filter(None);
+        assert_eq!(
+            state.take_events(),
+            [
+                StateEvent::SelectionChanged(Some(1)),
+                StateEvent::FilterCleared,
+                StateEvent::FilterChanged(None),
+            ]
+        );
+        assert!(state.take_events().is_empty());
+
+        state.increment_selected();
+        state.set_record_events(false);
+        assert!(state.take_events().is_empty());
+    }
+
+    #[test]
+    fn item_updates_keep_the_filtered_selection() {
+        let mut state = state(&["ant", "anteater", "bee", "antelope"]);
+        state.set_filter(Some("ant"));
+        state.select(Some(1));
+        let selected = state.original_index(1);
+
+        state.update_item(2, FuzzyListItem::new("bumblebee"));
+        assert_eq!(
+            state.selected().and_then(|s| state.original_index(s)),
+            selected
+        );
+
+        state.append_items(vec![FuzzyListItem::new("giant")]);
+        assert_eq!(state.effective_len(), 4);
+        assert_eq!(
+            state.selected().and_then(|s| state.original_index(s)),
+            selected
+        );
+
+        // the selected item no longer matching leaves nothing selected
+        state.update_item(selected.unwrap(), FuzzyListItem::new("bee"));
+        assert_eq!(state.selected(), None);
+    }
+
+    #[test]
+    fn live_query_below_min_len_is_emptied() {
+        let mut state = state(&["abc", "xyz"]);
+        state.set_live_min_len(2);
+        state.set_record_events(true);
+        state.set_filter_live("a");
+        assert_eq!(state.get_filter(), None);
+        state.set_filter_live("ab");
+        assert_eq!(state.effective_len(), 1);
+        state.take_events();
+
+        state.set_filter_live("a");
+        assert_eq!(state.get_filter(), None);
+        assert_eq!(state.effective_len(), 2);
+        assert_eq!(
+            state.take_events(),
+            [StateEvent::FilterEmptied, StateEvent::FilterChanged(None)]
+        );
+    }
+
+    #[test]
+    fn haystack_hook_applies_to_best_match_and_changes() {
+        let mut state = state(&["alpha", "beta", "gamma"]);
+        state.set_haystack_fn(Some(Rc::new(|item| item.search_text().replace('b', "z"))));
+        state.set_filter(Some("zeta"));
+        assert_eq!(shown(&state), ["beta"]);
+        assert_eq!(state.changed_since("zeta"), Vec::<usize>::new());
+
+        state.set_filter(None);
+        assert_eq!(state.scroll_to_best_match("zeta", 5), Some(1));
+    }
+
+    #[test]
+    fn minimap_shades_buckets_and_jumps_on_click() {
+        let mut state = state(&[
+            "ab", "a-b", "xx", "yy", "a--b", "zz", "ab", "qq", "a---b", "ww", "ab",
+        ]);
+        state.set_passthrough(true);
+        state.set_filter(Some("ab"));
+        let list = FuzzyList::new(state.get_items()).minimap(true);
+        let buf = render(list, &mut state, 6, 4);
+        let column: Vec<String> = (0..4).map(|y| buf.get(5, y).symbol.clone()).collect();
+        // buckets of 2, 3, 3 and 3 items, the exact matches are the strongest
+        assert_eq!(column, ["█", "▓", "█", "█"]);
+        assert_eq!(row(&buf, 0), "ab   █");
+
+        assert!(!state.click_minimap(4, 2));
+        assert!(state.click_minimap(5, 2));
+        let list = FuzzyList::new(state.get_items()).minimap(true);
+        let buf = render(list, &mut state, 6, 4);
+        assert_eq!(row(&buf, 0), "zz   █");
+    }
+}